@@ -0,0 +1,221 @@
+use serde::Serialize;
+use sqlx::Row;
+use std::collections::HashMap;
+
+use super::{
+    open_pool,
+    output::{self, OutputFormat},
+    read_config, utils,
+};
+
+/// Timing rules for suggesting a friend, in days since the last memory with them.
+#[derive(Clone, Copy, Default, Debug, PartialEq)]
+pub struct ConnectionConstraint {
+    /// Don't suggest the friend until this many days have passed.
+    pub cooldown_days: Option<i64>,
+    /// Suggest the friend first once this many days have passed.
+    pub interval_days: Option<i64>,
+}
+
+/// Constraints keyed by friend name and by level name.
+#[derive(Default)]
+pub struct Constraints {
+    pub friends: HashMap<String, ConnectionConstraint>,
+    pub levels: HashMap<String, ConnectionConstraint>,
+}
+
+impl Constraints {
+    /// Combines the friend's own rules with the rules of their level, preferring the former.
+    pub fn for_friend(&self, name: &str, level: &str) -> ConnectionConstraint {
+        let friend_constraint = self.friends.get(name).copied().unwrap_or_default();
+        let level_constraint = self.levels.get(level).copied().unwrap_or_default();
+        ConnectionConstraint {
+            cooldown_days: friend_constraint
+                .cooldown_days
+                .or(level_constraint.cooldown_days),
+            interval_days: friend_constraint
+                .interval_days
+                .or(level_constraint.interval_days),
+        }
+    }
+}
+
+/// A friend or level constraint, flattened for output.
+#[derive(Serialize)]
+pub struct ConstraintRecord {
+    pub target: &'static str,
+    pub name: String,
+    pub cooldown_days: Option<i64>,
+    pub interval_days: Option<i64>,
+}
+
+impl ConstraintRecord {
+    pub fn new(target: &'static str, name: &str, constraint: &ConnectionConstraint) -> Self {
+        ConstraintRecord {
+            target,
+            name: name.to_owned(),
+            cooldown_days: constraint.cooldown_days,
+            interval_days: constraint.interval_days,
+        }
+    }
+}
+
+/// What a constraint applies to.
+pub enum ConstraintTarget {
+    Friend(String),
+    Level(String),
+}
+
+pub(super) async fn read_constraints() -> Result<Constraints, sqlx::Error> {
+    let pool = open_pool().await?;
+    let friend_rows = sqlx::query(
+        "SELECT friends.name, friend_constraints.cooldown_days, friend_constraints.interval_days
+            FROM friend_constraints
+            JOIN friends ON friends.id = friend_constraints.friend_id",
+    )
+    .fetch_all(&pool)
+    .await?;
+    let level_rows =
+        sqlx::query("SELECT level, cooldown_days, interval_days FROM level_constraints")
+            .fetch_all(&pool)
+            .await?;
+
+    let friends = friend_rows
+        .into_iter()
+        .map(|row| {
+            (
+                row.get("name"),
+                ConnectionConstraint {
+                    cooldown_days: row.get("cooldown_days"),
+                    interval_days: row.get("interval_days"),
+                },
+            )
+        })
+        .collect();
+    let levels = level_rows
+        .into_iter()
+        .map(|row| {
+            (
+                row.get("level"),
+                ConnectionConstraint {
+                    cooldown_days: row.get("cooldown_days"),
+                    interval_days: row.get("interval_days"),
+                },
+            )
+        })
+        .collect();
+    Ok(Constraints { friends, levels })
+}
+
+async fn upsert_constraint(
+    target: &ConstraintTarget,
+    constraint: ConnectionConstraint,
+) -> Result<(), sqlx::Error> {
+    let pool = open_pool().await?;
+    let query = match target {
+        ConstraintTarget::Friend(name) => sqlx::query(
+            "INSERT INTO friend_constraints (friend_id, cooldown_days, interval_days)
+                SELECT id, ?2, ?3 FROM friends WHERE name = ?1
+                ON CONFLICT (friend_id) DO UPDATE SET
+                    cooldown_days = COALESCE(excluded.cooldown_days, cooldown_days),
+                    interval_days = COALESCE(excluded.interval_days, interval_days)",
+        )
+        .bind(name),
+        ConstraintTarget::Level(level) => sqlx::query(
+            "INSERT INTO level_constraints (level, cooldown_days, interval_days)
+                VALUES (?1, ?2, ?3)
+                ON CONFLICT (level) DO UPDATE SET
+                    cooldown_days = COALESCE(excluded.cooldown_days, cooldown_days),
+                    interval_days = COALESCE(excluded.interval_days, interval_days)",
+        )
+        .bind(level),
+    };
+    query
+        .bind(constraint.cooldown_days)
+        .bind(constraint.interval_days)
+        .execute(&pool)
+        .await?;
+    Ok(())
+}
+
+async fn delete_constraint(target: &ConstraintTarget) -> Result<bool, sqlx::Error> {
+    let pool = open_pool().await?;
+    let query = match target {
+        ConstraintTarget::Friend(name) => sqlx::query(
+            "DELETE FROM friend_constraints
+                WHERE friend_id = (SELECT id FROM friends WHERE name = ?1)",
+        )
+        .bind(name),
+        ConstraintTarget::Level(level) => {
+            sqlx::query("DELETE FROM level_constraints WHERE level = ?1").bind(level)
+        }
+    };
+    let result = query.execute(&pool).await?;
+    Ok(result.rows_affected() > 0)
+}
+
+/// Sets cooldown and/or interval rules, keeping rules that are not given.
+pub async fn set_constraint(
+    target: ConstraintTarget,
+    cooldown_days: Option<i64>,
+    interval_days: Option<i64>,
+) {
+    if cooldown_days.is_none() && interval_days.is_none() {
+        println!("No changes requested");
+        return;
+    }
+
+    let config = match read_config().await {
+        Ok(config) => config,
+        Err(err) => {
+            eprintln!("Failed to read data: {err}");
+            return;
+        }
+    };
+    match target {
+        ConstraintTarget::Friend(ref name) if !utils::is_name_duplicate(&config, name) => {
+            println!("Name \"{name}\" not found");
+            return;
+        }
+        ConstraintTarget::Level(ref level) if utils::level_chance(&config, level).is_none() => {
+            println!("Level \"{level}\" not found");
+            return;
+        }
+        _ => {}
+    }
+
+    let constraint = ConnectionConstraint {
+        cooldown_days,
+        interval_days,
+    };
+    if let Err(err) = upsert_constraint(&target, constraint).await {
+        eprintln!("Failed to write data: {err}");
+    }
+}
+
+/// Removes all rules of a friend or level.
+pub async fn clear_constraint(target: ConstraintTarget) {
+    match delete_constraint(&target).await {
+        Ok(true) => {}
+        Ok(false) => println!("No constraint found"),
+        Err(err) => eprintln!("Failed to write data: {err}"),
+    }
+}
+
+/// Prints all friend and level rules.
+pub async fn show_constraints(format: OutputFormat) {
+    let constraints = match read_constraints().await {
+        Ok(constraints) => constraints,
+        Err(err) => {
+            eprintln!("Failed to read data: {err}");
+            return;
+        }
+    };
+
+    let records = utils::constraint_records(&constraints);
+    let rendered_list = match format {
+        OutputFormat::Text => utils::list_constraints(&records),
+        _ => output::render(format, &records),
+    };
+    println!("{rendered_list}");
+}
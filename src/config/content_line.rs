@@ -0,0 +1,178 @@
+/// A `NAME;PARAM=value:value` line as used by both vCard and iCalendar files.
+#[derive(Debug, PartialEq)]
+pub(super) struct ContentLine {
+    /// Upper-cased property name, without any `group.` prefix.
+    pub name: String,
+    /// Parameters with upper-cased names and unquoted values.
+    pub params: Vec<(String, String)>,
+    pub value: String,
+}
+
+impl ContentLine {
+    pub fn param(&self, name: &str) -> Option<&str> {
+        self.params
+            .iter()
+            .find(|(param_name, _)| param_name == name)
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+/// Joins folded lines, which continue with a leading space or tab.
+pub(super) fn unfold_lines(text: &str) -> Vec<String> {
+    let mut lines: Vec<String> = Vec::new();
+    for line in text.lines() {
+        match (line.strip_prefix([' ', '\t']), lines.last_mut()) {
+            (Some(continuation), Some(last_line)) => last_line.push_str(continuation),
+            _ => lines.push(line.to_owned()),
+        }
+    }
+    lines
+}
+
+/// Folds `line` into lines of at most 75 bytes, each ending with CRLF, as both formats
+/// recommend.
+pub(super) fn fold_line(line: &str) -> String {
+    let mut folded = String::new();
+    let mut line_length = 0;
+    for char in line.chars() {
+        if line_length + char.len_utf8() > 75 {
+            folded.push_str("\r\n ");
+            line_length = 1;
+        }
+        folded.push(char);
+        line_length += char.len_utf8();
+    }
+    folded + "\r\n"
+}
+
+/// Splits a content line into its name, parameters and raw value. Separators inside
+/// double-quoted parameter values are ignored.
+pub(super) fn parse_content_line(line: &str) -> Option<ContentLine> {
+    let mut parts = vec![String::new()];
+    let mut value = None;
+    let mut in_quotes = false;
+    for (index, char) in line.char_indices() {
+        match char {
+            '"' => in_quotes = !in_quotes,
+            ';' if !in_quotes => parts.push(String::new()),
+            ':' if !in_quotes => {
+                value = Some(line[index + 1..].to_owned());
+                break;
+            }
+            _ => parts.last_mut().unwrap().push(char),
+        }
+    }
+
+    let mut parts = parts.into_iter();
+    let name = parts.next()?;
+    let name = name.rsplit('.').next().unwrap_or_default().to_uppercase();
+    let params = parts
+        .filter_map(|param| {
+            let (name, value) = param.split_once('=')?;
+            Some((name.to_uppercase(), value.to_owned()))
+        })
+        .collect();
+    Some(ContentLine {
+        name,
+        params,
+        value: value?,
+    })
+}
+
+pub(super) fn escape_value(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace(',', "\\,")
+        .replace(';', "\\;")
+        .replace('\n', "\\n")
+}
+
+/// Unescapes a value and splits it on unescaped `separator`, if any.
+fn unescape_values(value: &str, separator: Option<char>) -> Vec<String> {
+    let mut values = vec![String::new()];
+    let mut chars = value.chars();
+    while let Some(char) = chars.next() {
+        match char {
+            '\\' => match chars.next() {
+                Some('n' | 'N') => values.last_mut().unwrap().push('\n'),
+                Some(escaped) => values.last_mut().unwrap().push(escaped),
+                None => {}
+            },
+            _ if Some(char) == separator => values.push(String::new()),
+            _ => values.last_mut().unwrap().push(char),
+        }
+    }
+    values
+}
+
+/// Unescapes a single-valued property.
+pub(super) fn unescape_value(value: &str) -> String {
+    unescape_values(value, None).concat()
+}
+
+/// Unescapes a comma-separated list, dropping empty items.
+pub(super) fn unescape_list(value: &str) -> Vec<String> {
+    unescape_values(value, Some(','))
+        .into_iter()
+        .map(|item| item.trim().to_owned())
+        .filter(|item| !item.is_empty())
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::{
+        fold_line, parse_content_line, unescape_list, unescape_value, unfold_lines, ContentLine,
+    };
+
+    #[test]
+    fn test_unfold_lines() {
+        assert_eq!(
+            unfold_lines("FN:Ne\r\n gar\r\nNOTE:a\n\tb"),
+            vec!["FN:Negar".to_owned(), "NOTE:ab".to_owned()]
+        );
+    }
+
+    #[test]
+    fn test_fold_line() {
+        let line = format!("NOTE:{}", "é".repeat(50));
+        let folded = fold_line(&line);
+
+        assert!(folded.split("\r\n").all(|part| part.len() <= 75));
+        assert_eq!(folded.matches("\r\n").count(), 2);
+        assert_eq!(unfold_lines(&folded), vec![line]);
+        assert_eq!(fold_line("FN:Negar"), "FN:Negar\r\n");
+    }
+
+    #[test]
+    fn test_parse_content_line() {
+        assert_eq!(
+            parse_content_line("ATTENDEE;CN=\"Doe; John\";ROLE=CHAIR:mailto:john@example.com"),
+            Some(ContentLine {
+                name: "ATTENDEE".to_owned(),
+                params: vec![
+                    ("CN".to_owned(), "Doe; John".to_owned()),
+                    ("ROLE".to_owned(), "CHAIR".to_owned()),
+                ],
+                value: "mailto:john@example.com".to_owned(),
+            })
+        );
+        assert_eq!(
+            parse_content_line("item1.fn:Negar").map(|line| line.name),
+            Some("FN".to_owned())
+        );
+        assert_eq!(parse_content_line("no value"), None);
+    }
+
+    #[test]
+    fn test_unescape() {
+        assert_eq!(
+            unescape_value("Line\\nbreak\\, done\\\\"),
+            "Line\nbreak, done\\"
+        );
+        assert_eq!(
+            unescape_list("work, book club,,a\\,b"),
+            vec!["work".to_owned(), "book club".to_owned(), "a,b".to_owned()]
+        );
+    }
+}
@@ -0,0 +1,381 @@
+use rand::Rng;
+use serde::Serialize;
+use sqlx::{QueryBuilder, Row, Sqlite};
+use std::{
+    collections::{HashMap, HashSet},
+    io::{self, BufRead, Write},
+};
+
+use super::{
+    add_memory, draw_suggestions, open_pool,
+    output::{self, OutputFormat},
+    read_filtered_suggestion_context, utils, MemoryDetails, SuggestOptions, Suggestion,
+};
+
+/// A stored suggestion and what was done with it.
+#[derive(Serialize)]
+pub struct SuggestionRecord {
+    pub id: i64,
+    pub name: String,
+    pub level: String,
+    /// Percentage probability the friend had when suggested, unknown for early suggestions.
+    pub probability: Option<f64>,
+    /// `accepted`, `skipped` or `snoozed`, if the suggestion was answered.
+    pub outcome: Option<String>,
+    pub suggested_at: String,
+}
+
+/// Snooze length used when the answer doesn't give one.
+const DEFAULT_SNOOZE_DAYS: u32 = 7;
+
+/// What to do with a suggested friend.
+#[derive(Debug, PartialEq)]
+enum Answer {
+    Accept,
+    Skip,
+    /// Snooze for the given number of days, if any was given.
+    Snooze(Option<u32>),
+    Quit,
+}
+
+impl Answer {
+    /// Value of `suggestions.outcome` for this answer, if it is recorded.
+    fn outcome(&self) -> Option<&'static str> {
+        match self {
+            Answer::Accept => Some("accepted"),
+            Answer::Skip => Some("skipped"),
+            Answer::Snooze(_) => Some("snoozed"),
+            Answer::Quit => None,
+        }
+    }
+}
+
+/// Parses `a`, `s`, `n [days]` or `q`, or their long forms.
+fn parse_answer(input: &str) -> Option<Answer> {
+    let input = input.trim().to_lowercase();
+    let (command, argument) = match input.split_once(char::is_whitespace) {
+        Some((command, argument)) => (command, Some(argument.trim())),
+        None => (input.as_str(), None),
+    };
+    match (command, argument) {
+        ("a" | "accept", None) => Some(Answer::Accept),
+        ("s" | "skip", None) => Some(Answer::Skip),
+        ("n" | "snooze", None) => Some(Answer::Snooze(None)),
+        ("n" | "snooze", Some(days)) => parse_snooze_days(days).map(Some).map(Answer::Snooze),
+        ("q" | "quit", None) => Some(Answer::Quit),
+        _ => None,
+    }
+}
+
+fn parse_snooze_days(input: &str) -> Option<u32> {
+    input.trim().parse().ok().filter(|days| *days > 0)
+}
+
+/// Writes `question` to `output` and reads one line from `input`, or `None` once input has
+/// ended.
+fn prompt(question: &str, input: &mut impl BufRead, output: &mut impl Write) -> Option<String> {
+    write!(output, "{question}").ok()?;
+    output.flush().ok()?;
+    let mut line = String::new();
+    match input.read_line(&mut line) {
+        Ok(0) | Err(_) => None,
+        Ok(_) => Some(line.trim().to_owned()),
+    }
+}
+
+/// Asks what to do with a suggestion until the answer is valid, then how many days to snooze
+/// if it didn't say. Ending input quits.
+fn read_answer(input: &mut impl BufRead, output: &mut impl Write) -> Answer {
+    let answer = loop {
+        let Some(line) = prompt(
+            "[a]ccept, [s]kip, s[n]ooze [days] or [q]uit? ",
+            input,
+            output,
+        ) else {
+            return Answer::Quit;
+        };
+        match parse_answer(&line) {
+            Some(answer) => break answer,
+            None => {
+                let _ = writeln!(output, "Please answer a, s, n [days] or q");
+            }
+        }
+    };
+    if answer != Answer::Snooze(None) {
+        return answer;
+    }
+    let question = format!("Snooze for how many days? [{DEFAULT_SNOOZE_DAYS}] ");
+    loop {
+        match prompt(&question, input, output) {
+            Some(days) if days.is_empty() => return Answer::Snooze(None),
+            Some(days) => match parse_snooze_days(&days) {
+                Some(days) => return Answer::Snooze(Some(days)),
+                None => {
+                    let _ = writeln!(output, "Days must be a positive number");
+                }
+            },
+            None => return Answer::Quit,
+        }
+    }
+}
+
+/// Counts, per friend, the suggestions skipped since the latest memory with them.
+pub(super) async fn read_skip_counts() -> Result<HashMap<String, i64>, sqlx::Error> {
+    let pool = open_pool().await?;
+    let rows = sqlx::query(
+        "SELECT friends.name, COUNT(*) AS skips
+            FROM suggestions
+            JOIN friends ON friends.id = suggestions.friend_id
+            WHERE suggestions.outcome = 'skipped'
+                AND suggestions.suggested_at > COALESCE((
+                    SELECT MAX(memories.created_at) FROM memories
+                    JOIN memory_participants ON memory_participants.memory_id = memories.id
+                    WHERE memory_participants.friend_id = friends.id
+                ), '')
+            GROUP BY friends.id",
+    )
+    .fetch_all(&pool)
+    .await?;
+    Ok(rows
+        .into_iter()
+        .map(|row| (row.get("name"), row.get("skips")))
+        .collect())
+}
+
+/// Reads the names of friends snoozed until later.
+pub(super) async fn read_snoozed_names() -> Result<HashSet<String>, sqlx::Error> {
+    let pool = open_pool().await?;
+    let rows = sqlx::query(
+        "SELECT friends.name FROM snoozes
+            JOIN friends ON friends.id = snoozes.friend_id
+            WHERE snoozes.until > datetime('now')",
+    )
+    .fetch_all(&pool)
+    .await?;
+    Ok(rows.into_iter().map(|row| row.get("name")).collect())
+}
+
+/// Reads the level of the friend suggested last, if any.
+pub(super) async fn read_last_suggested_level() -> Result<Option<String>, sqlx::Error> {
+    let pool = open_pool().await?;
+    let row = sqlx::query(
+        "SELECT friends.level FROM suggestions
+            JOIN friends ON friends.id = suggestions.friend_id
+            ORDER BY suggestions.suggested_at DESC, suggestions.id DESC
+            LIMIT 1",
+    )
+    .fetch_optional(&pool)
+    .await?;
+    Ok(row.map(|row| row.get("level")))
+}
+
+async fn insert_suggestion(suggestion: &Suggestion) -> Result<i64, sqlx::Error> {
+    let pool = open_pool().await?;
+    let result = sqlx::query(
+        "INSERT INTO suggestions (friend_id, probability)
+            SELECT id, ?2 FROM friends WHERE name = ?1",
+    )
+    .bind(&suggestion.name)
+    .bind(suggestion.probability)
+    .execute(&pool)
+    .await?;
+    Ok(result.last_insert_rowid())
+}
+
+/// Stores suggestions without an outcome.
+pub(super) async fn record_suggestions(suggestions: &[Suggestion]) -> Result<(), sqlx::Error> {
+    for suggestion in suggestions {
+        insert_suggestion(suggestion).await?;
+    }
+    Ok(())
+}
+
+async fn read_suggestion_history(
+    friend: Option<&str>,
+    limit: Option<i64>,
+) -> Result<Vec<SuggestionRecord>, sqlx::Error> {
+    let pool = open_pool().await?;
+    let mut query = QueryBuilder::<Sqlite>::new(
+        "SELECT suggestions.id, friends.name, friends.level, suggestions.probability,
+            suggestions.outcome, suggestions.suggested_at
+            FROM suggestions
+            JOIN friends ON friends.id = suggestions.friend_id",
+    );
+    if let Some(friend) = friend {
+        query.push(" WHERE friends.name = ").push_bind(friend);
+    }
+    query
+        .push(" ORDER BY suggestions.suggested_at DESC, suggestions.id DESC LIMIT ")
+        .push_bind(limit.unwrap_or(-1));
+    let rows = query.build().fetch_all(&pool).await?;
+    Ok(rows
+        .into_iter()
+        .map(|row| SuggestionRecord {
+            id: row.get("id"),
+            name: row.get("name"),
+            level: row.get("level"),
+            probability: row.get("probability"),
+            outcome: row.get("outcome"),
+            suggested_at: row.get("suggested_at"),
+        })
+        .collect())
+}
+
+async fn update_outcome(id: i64, outcome: &str) -> Result<(), sqlx::Error> {
+    let pool = open_pool().await?;
+    sqlx::query("UPDATE suggestions SET outcome = ?1 WHERE id = ?2")
+        .bind(outcome)
+        .bind(id)
+        .execute(&pool)
+        .await?;
+    Ok(())
+}
+
+async fn snooze_friend(name: &str, days: u32) -> Result<(), sqlx::Error> {
+    let pool = open_pool().await?;
+    sqlx::query(
+        "INSERT OR REPLACE INTO snoozes (friend_id, until)
+            SELECT id, datetime('now', ?2) FROM friends WHERE name = ?1",
+    )
+    .bind(name)
+    .bind(format!("+{days} days"))
+    .execute(&pool)
+    .await?;
+    Ok(())
+}
+
+/// Asks for a memory kind until a memory with `name` is recorded, or input ends. Returns
+/// whether a memory was recorded.
+async fn record_accepted_memory(
+    name: &str,
+    kind_names: &[String],
+    input: &mut impl BufRead,
+    output: &mut impl Write,
+) -> bool {
+    let question = format!("Memory kind ({}): ", kind_names.join(", "));
+    while let Some(kind) = prompt(&question, input, output) {
+        if add_memory(&kind, &[name.to_owned()], None, MemoryDetails::default()).await {
+            return true;
+        }
+    }
+    false
+}
+
+/// Suggests friends one at a time, drawn with `rng`, and asks whether to log a memory with
+/// them, skip them and draw again, or snooze them for some days. Every answer is recorded,
+/// accepting only once the memory is logged.
+pub async fn suggest_interactively<R: Rng + ?Sized>(mut options: SuggestOptions, rng: &mut R) {
+    let mut input = io::stdin().lock();
+    let mut output = io::stdout();
+    loop {
+        let Some(context) = read_filtered_suggestion_context(&options).await else {
+            return;
+        };
+        let mut strategy = context.strategy(options.strategy, options.temperature);
+        let Some(suggestion) = draw_suggestions(&context, 1, None, &mut *strategy, rng).pop()
+        else {
+            println!("No friend to suggest");
+            return;
+        };
+        println!("Suggested friend: {}", suggestion.name);
+        let suggestion_id = match insert_suggestion(&suggestion).await {
+            Ok(suggestion_id) => suggestion_id,
+            Err(err) => {
+                eprintln!("Failed to write data: {err}");
+                return;
+            }
+        };
+
+        let answer = read_answer(&mut input, &mut output);
+        match answer {
+            Answer::Accept => {
+                let kind_names = utils::sort_kinds(&context.config)
+                    .iter()
+                    .map(|kind| kind.name.clone())
+                    .collect::<Vec<String>>();
+                let is_recorded =
+                    record_accepted_memory(&suggestion.name, &kind_names, &mut input, &mut output)
+                        .await;
+                if !is_recorded {
+                    return;
+                }
+            }
+            Answer::Skip => options.filter.exclude.push(suggestion.name.clone()),
+            Answer::Snooze(days) => {
+                let days = days.unwrap_or(DEFAULT_SNOOZE_DAYS);
+                if let Err(err) = snooze_friend(&suggestion.name, days).await {
+                    eprintln!("Failed to write data: {err}");
+                    return;
+                }
+                println!("Snoozed {} for {days} day(s)", suggestion.name);
+            }
+            Answer::Quit => return,
+        }
+        if let Some(outcome) = answer.outcome() {
+            if let Err(err) = update_outcome(suggestion_id, outcome).await {
+                eprintln!("Failed to write data: {err}");
+                return;
+            }
+        }
+        if answer == Answer::Accept {
+            return;
+        }
+    }
+}
+
+/// Lists past suggestions, newest first.
+pub async fn list_suggestion_history(
+    friend: Option<String>,
+    limit: Option<i64>,
+    format: OutputFormat,
+) {
+    let history = match read_suggestion_history(friend.as_deref(), limit).await {
+        Ok(history) => history,
+        Err(err) => {
+            eprintln!("Failed to read suggestions: {err}");
+            return;
+        }
+    };
+
+    println!("{}", output::render(format, &history));
+}
+
+#[cfg(test)]
+mod test {
+    use std::io::Cursor;
+
+    use super::{parse_answer, read_answer, Answer};
+
+    #[test]
+    fn test_parse_answer() {
+        assert_eq!(parse_answer("a"), Some(Answer::Accept));
+        assert_eq!(parse_answer(" Skip "), Some(Answer::Skip));
+        assert_eq!(parse_answer("n"), Some(Answer::Snooze(None)));
+        assert_eq!(parse_answer("snooze 14"), Some(Answer::Snooze(Some(14))));
+        assert_eq!(parse_answer("q"), Some(Answer::Quit));
+        assert_eq!(parse_answer("n 0"), None);
+        assert_eq!(parse_answer("s 3"), None);
+        assert_eq!(parse_answer("maybe"), None);
+    }
+
+    #[test]
+    fn test_read_answer() {
+        let answer = |lines: &str| {
+            let mut output = Vec::new();
+            let answer = read_answer(&mut Cursor::new(lines), &mut output);
+            (answer, String::from_utf8(output).unwrap())
+        };
+
+        let (skip, output) = answer("maybe\ns\n");
+        assert_eq!(skip, Answer::Skip);
+        assert!(output.contains("Please answer a, s, n [days] or q"));
+        assert_eq!(answer("a\n").0, Answer::Accept);
+        assert_eq!(answer("n 3\n").0, Answer::Snooze(Some(3)));
+        assert_eq!(answer("n\n\n").0, Answer::Snooze(None));
+        let (snooze, output) = answer("n\n0\n5\n");
+        assert_eq!(snooze, Answer::Snooze(Some(5)));
+        assert!(output.contains("Days must be a positive number"));
+        assert_eq!(answer("").0, Answer::Quit);
+        assert_eq!(answer("n\n").0, Answer::Quit);
+    }
+}
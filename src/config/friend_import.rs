@@ -0,0 +1,251 @@
+use std::{collections::HashSet, fs, path::Path};
+
+use super::{open_pool, output, read_config, utils, AkcConfig};
+
+/// A friend read from an import file.
+pub(super) struct ImportedFriend {
+    /// Where the friend was found in the file, e.g. "Row 2".
+    pub origin: String,
+    pub name: String,
+    pub level: String,
+    pub note: Option<String>,
+    pub tags: Vec<String>,
+}
+
+fn split_tags(tags: &str) -> Vec<String> {
+    tags.split([',', ';'])
+        .map(str::trim)
+        .filter(|tag| !tag.is_empty())
+        .map(str::to_owned)
+        .collect()
+}
+
+/// Reads friends from CSV rows with a header of `name`, `level` and optionally `notes` and
+/// `tags` columns, in any order.
+fn friends_from_csv(rows: Vec<Vec<String>>) -> Result<Vec<ImportedFriend>, String> {
+    let mut rows = rows.into_iter();
+    let headers = rows
+        .next()
+        .ok_or_else(|| "The file is empty".to_owned())?
+        .iter()
+        .map(|header| header.trim().to_lowercase())
+        .collect::<Vec<String>>();
+    let column = |names: &[&str]| {
+        headers
+            .iter()
+            .position(|header| names.contains(&header.as_str()))
+    };
+    let (Some(name_column), Some(level_column)) = (column(&["name"]), column(&["level"])) else {
+        return Err("The header must have name and level columns".to_owned());
+    };
+    let note_column = column(&["notes", "note"]);
+    let tags_column = column(&["tags", "tag"]);
+
+    let friends = rows
+        .enumerate()
+        .map(|(index, row)| {
+            let field = |column: Option<usize>| {
+                column
+                    .and_then(|column| row.get(column))
+                    .map(|field| field.trim().to_owned())
+                    .unwrap_or_default()
+            };
+            let note = field(note_column);
+            ImportedFriend {
+                // The header is row 1.
+                origin: format!("Row {}", index + 2),
+                name: field(Some(name_column)),
+                level: field(Some(level_column)),
+                note: (!note.is_empty()).then_some(note),
+                tags: split_tags(&field(tags_column)),
+            }
+        })
+        .collect();
+    Ok(friends)
+}
+
+/// Returns why each friend can't be added, or `None` if they can.
+fn check_imported_friends(config: &AkcConfig, friends: &[ImportedFriend]) -> Vec<Option<String>> {
+    let mut seen_names = HashSet::new();
+    friends
+        .iter()
+        .map(|friend| {
+            if friend.name.is_empty() {
+                Some("Name is empty".to_owned())
+            } else if utils::is_name_duplicate(config, &friend.name) {
+                Some(format!("Name \"{}\" already exists", friend.name))
+            } else if !seen_names.insert(friend.name.as_str()) {
+                Some(format!("Name \"{}\" appears more than once", friend.name))
+            } else if utils::level_chance(config, &friend.level).is_none() {
+                Some(format!("Level \"{}\" not found", friend.level))
+            } else {
+                None
+            }
+        })
+        .collect()
+}
+
+async fn save_imported_friends(friends: &[&ImportedFriend]) -> Result<(), sqlx::Error> {
+    let pool = open_pool().await?;
+    let mut transaction = pool.begin().await?;
+    for friend in friends {
+        let friend_id = sqlx::query(
+            "INSERT INTO friends (name, chance, level, note)
+                SELECT ?1, chance, name, ?3 FROM levels WHERE name = ?2",
+        )
+        .bind(&friend.name)
+        .bind(&friend.level)
+        .bind(&friend.note)
+        .execute(&mut *transaction)
+        .await?
+        .last_insert_rowid();
+        for tag in &friend.tags {
+            sqlx::query("INSERT OR IGNORE INTO friend_tags (friend_id, tag) VALUES (?1, ?2)")
+                .bind(friend_id)
+                .bind(tag)
+                .execute(&mut *transaction)
+                .await?;
+        }
+    }
+    transaction.commit().await
+}
+
+/// Adds every valid friend and reports the others, or only previews with `dry_run`.
+pub(super) async fn import_friends(friends: Vec<ImportedFriend>, dry_run: bool) {
+    let config = match read_config().await {
+        Ok(config) => config,
+        Err(err) => {
+            eprintln!("Failed to read data: {err}");
+            return;
+        }
+    };
+
+    let mut valid_friends = Vec::new();
+    for (friend, error) in friends
+        .iter()
+        .zip(check_imported_friends(&config, &friends))
+    {
+        match error {
+            Some(error) => println!("{}: {error}", friend.origin),
+            None if dry_run => println!(
+                "{}: Would add \"{}\" as {}",
+                friend.origin, friend.name, friend.level
+            ),
+            None => valid_friends.push(friend),
+        }
+    }
+
+    let skipped_count = friends.len() - valid_friends.len();
+    if dry_run {
+        return;
+    }
+    if !valid_friends.is_empty() {
+        if let Err(err) = save_imported_friends(&valid_friends).await {
+            eprintln!("Failed to write data: {err}");
+            return;
+        }
+    }
+    println!(
+        "Added {} friend(s), skipped {skipped_count}",
+        valid_friends.len()
+    );
+}
+
+/// Imports friends from a CSV file.
+pub async fn import_friends_csv(path: &Path, dry_run: bool) {
+    let text = match fs::read_to_string(path) {
+        Ok(text) => text,
+        Err(err) => {
+            eprintln!("Failed to read {}: {err}", path.display());
+            return;
+        }
+    };
+    let friends = match friends_from_csv(output::parse_csv(&text)) {
+        Ok(friends) => friends,
+        Err(err) => {
+            println!("{err}");
+            return;
+        }
+    };
+
+    import_friends(friends, dry_run).await
+}
+
+#[cfg(test)]
+mod test {
+    use super::{check_imported_friends, friends_from_csv, split_tags};
+    use crate::config::{friend_info, AkcConfig, LevelInfo};
+
+    fn row(fields: &[&str]) -> Vec<String> {
+        fields.iter().map(|field| field.to_string()).collect()
+    }
+
+    #[test]
+    fn test_split_tags() {
+        assert_eq!(
+            split_tags("work, climbing;;book club "),
+            vec![
+                "work".to_owned(),
+                "climbing".to_owned(),
+                "book club".to_owned()
+            ]
+        );
+        assert!(split_tags(" ").is_empty());
+    }
+
+    #[test]
+    fn test_friends_from_csv() {
+        let friends = friends_from_csv(vec![
+            row(&["Level", "Name", "Tags"]),
+            row(&["aji", " Negar ", "work; climbing"]),
+            row(&["ki"]),
+        ])
+        .unwrap();
+
+        assert_eq!(friends.len(), 2);
+        assert_eq!(friends[0].origin, "Row 2");
+        assert_eq!(friends[0].name, "Negar");
+        assert_eq!(friends[0].level, "aji");
+        assert_eq!(friends[0].note, None);
+        assert_eq!(
+            friends[0].tags,
+            vec!["work".to_owned(), "climbing".to_owned()]
+        );
+        assert_eq!(friends[1].name, "");
+
+        assert!(friends_from_csv(vec![row(&["name", "notes"])]).is_err());
+        assert!(friends_from_csv(Vec::new()).is_err());
+    }
+
+    #[test]
+    fn test_check_imported_friends() {
+        let config = AkcConfig {
+            friends: vec![friend_info("Arash", "ki", 5.0)],
+            levels: vec![LevelInfo {
+                name: "aji".to_owned(),
+                chance: 50.0,
+            }],
+            ..Default::default()
+        };
+        let friends = friends_from_csv(vec![
+            row(&["name", "level"]),
+            row(&["Negar", "aji"]),
+            row(&["Arash", "aji"]),
+            row(&["Negar", "aji"]),
+            row(&["Sara", "family"]),
+            row(&["", "aji"]),
+        ])
+        .unwrap();
+
+        assert_eq!(
+            check_imported_friends(&config, &friends),
+            vec![
+                None,
+                Some("Name \"Arash\" already exists".to_owned()),
+                Some("Name \"Negar\" appears more than once".to_owned()),
+                Some("Level \"family\" not found".to_owned()),
+                Some("Name is empty".to_owned()),
+            ]
+        );
+    }
+}
@@ -0,0 +1,343 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fs,
+    path::Path,
+};
+
+use super::{
+    content_line::{parse_content_line, unescape_list, unescape_value, unfold_lines},
+    memory::{parse_date_input, resolve_date, DateInput},
+    read_config, read_memories, rebuild_chances_from_memories, save_memory, utils, MemoryDetails,
+};
+
+/// The parts of a `VEVENT` that matter for memories.
+#[derive(Debug, Default, PartialEq)]
+struct CalendarEvent {
+    summary: Option<String>,
+    /// Raw `DTSTART` value, e.g. `20240301T183000Z`.
+    start: Option<String>,
+    location: Option<String>,
+    /// Attendee common names, or their addresses if they have none.
+    attendees: Vec<String>,
+    categories: Vec<String>,
+}
+
+/// Parses every `VEVENT` in `text`.
+fn parse_events(text: &str) -> Vec<CalendarEvent> {
+    let mut events = Vec::new();
+    let mut event: Option<CalendarEvent> = None;
+    for line in unfold_lines(text) {
+        let Some(line) = parse_content_line(&line) else {
+            continue;
+        };
+        let value = &line.value;
+        match (line.name.as_str(), event.as_mut()) {
+            ("BEGIN", _) if value.eq_ignore_ascii_case("VEVENT") => {
+                event = Some(CalendarEvent::default())
+            }
+            ("END", Some(_)) if value.eq_ignore_ascii_case("VEVENT") => events.extend(event.take()),
+            ("SUMMARY", Some(event)) => event.summary = Some(unescape_value(value)),
+            ("DTSTART", Some(event)) => event.start = Some(value.trim().to_owned()),
+            ("LOCATION", Some(event)) => event.location = Some(unescape_value(value)),
+            ("CATEGORIES", Some(event)) => event.categories.extend(unescape_list(value)),
+            ("ATTENDEE", Some(event)) => event.attendees.push(match line.param("CN") {
+                Some(name) => name.to_owned(),
+                None => value.trim_start_matches("mailto:").to_owned(),
+            }),
+            _ => {}
+        }
+    }
+    events
+}
+
+/// Converts a `DTSTART` value like `20240301T183000Z` or `20240301` to a stored timestamp.
+/// Times without a `Z` suffix are kept as written, since time zones can't be resolved.
+fn parse_event_start(value: &str) -> Option<String> {
+    let value = value.strip_suffix(['Z', 'z']).unwrap_or(value);
+    let (date, time) = value.split_once(['T', 't']).unwrap_or((value, "000000"));
+    if date.len() != 8 || time.len() != 6 || !value.is_ascii() {
+        return None;
+    }
+    let datetime = format!(
+        "{}-{}-{} {}:{}:{}",
+        &date[..4],
+        &date[4..6],
+        &date[6..],
+        &time[..2],
+        &time[2..4],
+        &time[4..]
+    );
+    match parse_date_input(&datetime)? {
+        DateInput::Absolute(datetime) => Some(datetime),
+        DateInput::DaysAgo(_) => None,
+    }
+}
+
+/// Returns whether `text` contains `word` surrounded by non-alphanumeric characters,
+/// ignoring case.
+fn contains_word(text: &str, word: &str) -> bool {
+    let text = text.to_lowercase();
+    let word = word.to_lowercase();
+    !word.is_empty()
+        && text.match_indices(&word).any(|(index, _)| {
+            let before = text[..index].chars().next_back();
+            let after = text[index + word.len()..].chars().next();
+            !before.is_some_and(char::is_alphanumeric) && !after.is_some_and(char::is_alphanumeric)
+        })
+}
+
+/// Finds the friends taking part in `event`, either as attendees or by being named in its
+/// summary.
+fn match_friend_names(event: &CalendarEvent, friend_names: &[String]) -> Vec<String> {
+    friend_names
+        .iter()
+        .filter(|name| {
+            event
+                .attendees
+                .iter()
+                .any(|attendee| attendee.trim().eq_ignore_ascii_case(name))
+                || event
+                    .summary
+                    .as_deref()
+                    .is_some_and(|summary| contains_word(summary, name))
+        })
+        .cloned()
+        .collect()
+}
+
+/// Picks the memory kind of the first mapped category, or `default_kind`.
+fn event_kind(
+    event: &CalendarEvent,
+    mapping: &HashMap<String, String>,
+    default_kind: Option<&str>,
+) -> Option<String> {
+    event
+        .categories
+        .iter()
+        .find_map(|category| mapping.get(&category.to_lowercase()).cloned())
+        .or_else(|| default_kind.map(str::to_owned))
+}
+
+/// Reads a JSON object mapping calendar categories to memory kinds.
+fn read_mapping(path: &Path) -> Result<HashMap<String, String>, String> {
+    let json = fs::read_to_string(path)
+        .map_err(|err| format!("Failed to read {}: {err}", path.display()))?;
+    let mapping: HashMap<String, String> = serde_json::from_str(&json)
+        .map_err(|err| format!("Invalid mapping file {}: {err}", path.display()))?;
+    Ok(mapping
+        .into_iter()
+        .map(|(category, kind)| (category.to_lowercase(), kind))
+        .collect())
+}
+
+/// Adds past calendar events as memories of the friends they match. The kind comes from
+/// `mapping_path` by category, falling back to `default_kind`.
+pub async fn import_ics(
+    path: &Path,
+    mapping_path: Option<&Path>,
+    default_kind: Option<&str>,
+    dry_run: bool,
+) {
+    let text = match fs::read_to_string(path) {
+        Ok(text) => text,
+        Err(err) => {
+            eprintln!("Failed to read {}: {err}", path.display());
+            return;
+        }
+    };
+    let mapping = match mapping_path.map(read_mapping).transpose() {
+        Ok(mapping) => mapping.unwrap_or_default(),
+        Err(err) => {
+            println!("{err}");
+            return;
+        }
+    };
+    let config = match read_config().await {
+        Ok(config) => config,
+        Err(err) => {
+            eprintln!("Failed to read data: {err}");
+            return;
+        }
+    };
+    let memories = match read_memories().await {
+        Ok(memories) => memories,
+        Err(err) => {
+            eprintln!("Failed to read memories: {err}");
+            return;
+        }
+    };
+    let friend_names = config
+        .friends
+        .iter()
+        .map(|friend| friend.name.clone())
+        .collect::<Vec<String>>();
+    // Events already imported, or added by hand with the same kind, time and participants, are
+    // skipped.
+    let mut existing_memories = memories
+        .iter()
+        .map(|memory| utils::memory_key(&memory.kind, &memory.created_at, &memory.names))
+        .collect::<HashSet<(String, String, Vec<String>)>>();
+
+    let events = parse_events(&text);
+    let mut added_count = 0;
+    for (index, event) in events.iter().enumerate() {
+        let origin = format!(
+            "Event {} ({})",
+            index + 1,
+            event.summary.as_deref().unwrap_or("no summary")
+        );
+        let Some(created_at) = event.start.as_deref().and_then(parse_event_start) else {
+            println!("{origin}: Start time is missing or invalid");
+            continue;
+        };
+        match resolve_date(&DateInput::Absolute(created_at.clone())).await {
+            Ok((_, false)) => {}
+            Ok((_, true)) => {
+                println!("{origin}: Starts in the future");
+                continue;
+            }
+            Err(err) => {
+                eprintln!("Failed to read data: {err}");
+                return;
+            }
+        }
+        let Some(kind) = event_kind(event, &mapping, default_kind) else {
+            println!("{origin}: No memory kind for its categories, please pass --kind");
+            continue;
+        };
+        if utils::kind_reduction(&config, &kind).is_none() {
+            println!("{origin}: Memory kind \"{kind}\" not found");
+            continue;
+        }
+        let names = match_friend_names(event, &friend_names);
+        if names.is_empty() {
+            println!("{origin}: No friends matched");
+            continue;
+        }
+        if !existing_memories.insert(utils::memory_key(&kind, &created_at, &names)) {
+            println!("{origin}: Already imported");
+            continue;
+        }
+
+        if dry_run {
+            println!(
+                "{origin}: Would add {kind} with {} at {created_at}",
+                names.join(", ")
+            );
+            continue;
+        }
+        let details = MemoryDetails {
+            note: event.summary.clone(),
+            place: event.location.clone(),
+            tags: event.categories.clone(),
+        };
+        if let Err(err) = save_memory(&kind, &names, Some(&created_at), &details).await {
+            eprintln!("Failed to write data: {err}");
+            return;
+        }
+        added_count += 1;
+    }
+
+    if dry_run {
+        return;
+    }
+    if added_count > 0 {
+        if let Err(err) = rebuild_chances_from_memories().await {
+            eprintln!("Failed to rebuild chances: {err}");
+            return;
+        }
+    }
+    println!(
+        "Imported {added_count} event(s), skipped {}",
+        events.len() - added_count
+    );
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashMap;
+
+    use super::{
+        contains_word, event_kind, match_friend_names, parse_event_start, parse_events,
+        CalendarEvent,
+    };
+
+    fn names(names: &[&str]) -> Vec<String> {
+        names.iter().map(|name| name.to_string()).collect()
+    }
+
+    #[test]
+    fn test_parse_events() {
+        let text = "BEGIN:VCALENDAR\r\nBEGIN:VEVENT\r\nSUMMARY:Climbing with Negar\r\n\
+            DTSTART;TZID=Europe/Berlin:20240301T183000\r\nLOCATION:Gym\\, north\r\n\
+            ATTENDEE;CN=\"Arash\":mailto:arash@example.com\r\n\
+            ATTENDEE:mailto:sara@example.com\r\nCATEGORIES:Sport,Outdoor\r\nEND:VEVENT\r\n\
+            SUMMARY:Not an event\r\nEND:VCALENDAR\r\n";
+
+        assert_eq!(
+            parse_events(text),
+            vec![CalendarEvent {
+                summary: Some("Climbing with Negar".to_owned()),
+                start: Some("20240301T183000".to_owned()),
+                location: Some("Gym, north".to_owned()),
+                attendees: names(&["Arash", "sara@example.com"]),
+                categories: names(&["Sport", "Outdoor"]),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_parse_event_start() {
+        assert_eq!(
+            parse_event_start("20240301T183000Z"),
+            Some("2024-03-01 18:30:00".to_owned())
+        );
+        assert_eq!(
+            parse_event_start("20240301"),
+            Some("2024-03-01 00:00:00".to_owned())
+        );
+        assert_eq!(parse_event_start("20240231T183000"), None);
+        assert_eq!(parse_event_start("2024-03-01"), None);
+    }
+
+    #[test]
+    fn test_contains_word() {
+        assert!(contains_word("Dinner with negar!", "Negar"));
+        assert!(contains_word("Sara", "sara"));
+        assert!(!contains_word("Alice's party", "Ali"));
+        assert!(!contains_word("Party", ""));
+    }
+
+    #[test]
+    fn test_match_friend_names() {
+        let event = CalendarEvent {
+            summary: Some("Dinner with Negar and Sara".to_owned()),
+            attendees: names(&["arash "]),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            match_friend_names(&event, &names(&["Arash", "Negar", "Ali", "Sara"])),
+            names(&["Arash", "Negar", "Sara"])
+        );
+    }
+
+    #[test]
+    fn test_event_kind() {
+        let mapping = HashMap::from([("sport".to_owned(), "climbing".to_owned())]);
+        let event = CalendarEvent {
+            categories: names(&["Work", "Sport"]),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            event_kind(&event, &mapping, Some("meet")),
+            Some("climbing".to_owned())
+        );
+        assert_eq!(
+            event_kind(&CalendarEvent::default(), &mapping, Some("meet")),
+            Some("meet".to_owned())
+        );
+        assert_eq!(event_kind(&CalendarEvent::default(), &mapping, None), None);
+    }
+}
@@ -0,0 +1,253 @@
+use sqlx::Row;
+
+use super::{
+    open_pool,
+    output::{self, OutputFormat},
+    read_config, rebuild_chances_from_memories, utils,
+};
+
+fn is_valid_reduction(reduction: f64) -> bool {
+    reduction.is_finite() && reduction > 0.0
+}
+
+async fn insert_kind(name: &str, reduction: f64) -> Result<(), sqlx::Error> {
+    let pool = open_pool().await?;
+    sqlx::query("INSERT INTO memory_kinds (name, reduction) VALUES (?1, ?2)")
+        .bind(name)
+        .bind(reduction)
+        .execute(&pool)
+        .await?;
+    Ok(())
+}
+
+async fn update_kind(
+    name: &str,
+    new_name: Option<&str>,
+    new_reduction: Option<f64>,
+) -> Result<(), sqlx::Error> {
+    let pool = open_pool().await?;
+    let mut transaction = pool.begin().await?;
+    if let Some(reduction) = new_reduction {
+        sqlx::query("UPDATE memory_kinds SET reduction = ?1 WHERE name = ?2")
+            .bind(reduction)
+            .bind(name)
+            .execute(&mut *transaction)
+            .await?;
+    }
+    if let Some(new_name) = new_name {
+        sqlx::query("UPDATE memory_kinds SET name = ?1 WHERE name = ?2")
+            .bind(new_name)
+            .bind(name)
+            .execute(&mut *transaction)
+            .await?;
+        sqlx::query("UPDATE memories SET kind = ?1 WHERE kind = ?2")
+            .bind(new_name)
+            .bind(name)
+            .execute(&mut *transaction)
+            .await?;
+        sqlx::query("UPDATE memory_trash SET kind = ?1 WHERE kind = ?2")
+            .bind(new_name)
+            .bind(name)
+            .execute(&mut *transaction)
+            .await?;
+        sqlx::query("UPDATE plans SET kind = ?1 WHERE kind = ?2")
+            .bind(new_name)
+            .bind(name)
+            .execute(&mut *transaction)
+            .await?;
+    }
+    transaction.commit().await
+}
+
+/// Rows that still refer to a memory kind.
+#[derive(Debug, Default, PartialEq)]
+struct KindUsage {
+    memories: i64,
+    /// Undone memories, which can be redone.
+    trashed_memories: i64,
+    plans: i64,
+}
+
+async fn read_kind_usage(name: &str) -> Result<KindUsage, sqlx::Error> {
+    let pool = open_pool().await?;
+    let row = sqlx::query(
+        "SELECT
+            (SELECT COUNT(*) FROM memories WHERE kind = ?1) AS memories,
+            (SELECT COUNT(*) FROM memory_trash WHERE kind = ?1) AS trashed_memories,
+            (SELECT COUNT(*) FROM plans WHERE kind = ?1) AS plans",
+    )
+    .bind(name)
+    .fetch_one(&pool)
+    .await?;
+    Ok(KindUsage {
+        memories: row.get("memories"),
+        trashed_memories: row.get("trashed_memories"),
+        plans: row.get("plans"),
+    })
+}
+
+/// Explains why a memory kind can't be removed, if anything still uses it.
+fn kind_in_use_message(name: &str, usage: &KindUsage) -> Option<String> {
+    let uses = [
+        (usage.memories, "memory(ies)"),
+        (usage.trashed_memories, "undone memory(ies)"),
+        (usage.plans, "plan(s)"),
+    ]
+    .into_iter()
+    .filter(|(count, _)| *count > 0)
+    .map(|(count, noun)| format!("{count} {noun}"))
+    .collect::<Vec<String>>();
+    if uses.is_empty() {
+        return None;
+    }
+    Some(format!(
+        "Memory kind \"{name}\" is still used by {}",
+        uses.join(", ")
+    ))
+}
+
+async fn delete_kind(name: &str) -> Result<bool, sqlx::Error> {
+    let pool = open_pool().await?;
+    let result = sqlx::query("DELETE FROM memory_kinds WHERE name = ?1")
+        .bind(name)
+        .execute(&pool)
+        .await?;
+    Ok(result.rows_affected() > 0)
+}
+
+/// Adds a memory kind with its chance reduction.
+pub async fn add_kind(name: String, reduction: f64) {
+    if !is_valid_reduction(reduction) {
+        println!("Reduction must be a positive number");
+        return;
+    }
+
+    let config = match read_config().await {
+        Ok(config) => config,
+        Err(err) => {
+            eprintln!("Failed to read data: {err}");
+            return;
+        }
+    };
+    if utils::kind_reduction(&config, &name).is_some() {
+        println!("Memory kind \"{name}\" already exists, please use a different name");
+        return;
+    }
+
+    if let Err(err) = insert_kind(&name, reduction).await {
+        eprintln!("Failed to write data: {err}");
+    }
+}
+
+/// Edits memory kind name and/or reduction.
+pub async fn edit_kind(name: String, new_name: Option<String>, new_reduction: Option<f64>) {
+    if new_name.is_none() && new_reduction.is_none() {
+        println!("No changes requested");
+        return;
+    }
+    if new_reduction.is_some_and(|reduction| !is_valid_reduction(reduction)) {
+        println!("Reduction must be a positive number");
+        return;
+    }
+
+    let config = match read_config().await {
+        Ok(config) => config,
+        Err(err) => {
+            eprintln!("Failed to read data: {err}");
+            return;
+        }
+    };
+    if utils::kind_reduction(&config, &name).is_none() {
+        println!("Memory kind \"{name}\" not found");
+        return;
+    }
+    if let Some(ref new_name_value) = new_name {
+        if *new_name_value != name && utils::kind_reduction(&config, new_name_value).is_some() {
+            println!(
+                "Memory kind \"{new_name_value}\" already exists, please use a different name"
+            );
+            return;
+        }
+    }
+
+    if let Err(err) = update_kind(&name, new_name.as_deref(), new_reduction).await {
+        eprintln!("Failed to write data: {err}");
+        return;
+    }
+
+    if new_reduction.is_some() {
+        if let Err(err) = rebuild_chances_from_memories().await {
+            eprintln!("Failed to rebuild chances: {err}");
+        }
+    }
+}
+
+/// Removes a memory kind that no memory uses anymore.
+pub async fn remove_kind(name: String) {
+    match read_kind_usage(&name).await {
+        Ok(usage) => {
+            if let Some(message) = kind_in_use_message(&name, &usage) {
+                println!("{message}");
+                return;
+            }
+        }
+        Err(err) => {
+            eprintln!("Failed to read data: {err}");
+            return;
+        }
+    }
+
+    match delete_kind(&name).await {
+        Ok(true) => {}
+        Ok(false) => println!("Memory kind \"{name}\" not found"),
+        Err(err) => eprintln!("Failed to write data: {err}"),
+    }
+}
+
+/// Lists memory kinds sorted by reduction.
+pub async fn list_kinds(format: OutputFormat) {
+    let config = match read_config().await {
+        Ok(config) => config,
+        Err(err) => {
+            eprintln!("Failed to read data: {err}");
+            return;
+        }
+    };
+
+    let rendered_list = match format {
+        OutputFormat::Text => utils::list_kinds(&config),
+        _ => output::render(format, utils::sort_kinds(&config)),
+    };
+    println!("{rendered_list}");
+}
+
+#[cfg(test)]
+mod test {
+    use super::{kind_in_use_message, KindUsage};
+
+    #[test]
+    fn test_kind_in_use_message() {
+        assert_eq!(kind_in_use_message("call", &KindUsage::default()), None);
+        assert_eq!(
+            kind_in_use_message(
+                "call",
+                &KindUsage {
+                    memories: 2,
+                    trashed_memories: 0,
+                    plans: 1,
+                }
+            ),
+            Some("Memory kind \"call\" is still used by 2 memory(ies), 1 plan(s)".to_owned())
+        );
+        assert_eq!(
+            kind_in_use_message(
+                "call",
+                &KindUsage {
+                    trashed_memories: 1,
+                    ..Default::default()
+                }
+            ),
+            Some("Memory kind \"call\" is still used by 1 undone memory(ies)".to_owned())
+        );
+    }
+}
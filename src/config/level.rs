@@ -0,0 +1,172 @@
+use sqlx::Row;
+
+use super::{
+    open_pool,
+    output::{self, OutputFormat},
+    read_config, rebuild_chances_from_memories, utils,
+};
+
+fn is_valid_chance(chance: f64) -> bool {
+    chance.is_finite() && chance > 0.0
+}
+
+async fn insert_level(name: &str, chance: f64) -> Result<(), sqlx::Error> {
+    let pool = open_pool().await?;
+    sqlx::query("INSERT INTO levels (name, chance) VALUES (?1, ?2)")
+        .bind(name)
+        .bind(chance)
+        .execute(&pool)
+        .await?;
+    Ok(())
+}
+
+async fn update_level(
+    name: &str,
+    new_name: Option<&str>,
+    new_chance: Option<f64>,
+) -> Result<(), sqlx::Error> {
+    let pool = open_pool().await?;
+    let mut transaction = pool.begin().await?;
+    if let Some(chance) = new_chance {
+        sqlx::query("UPDATE levels SET chance = ?1 WHERE name = ?2")
+            .bind(chance)
+            .bind(name)
+            .execute(&mut *transaction)
+            .await?;
+    }
+    if let Some(new_name) = new_name {
+        sqlx::query("UPDATE levels SET name = ?1 WHERE name = ?2")
+            .bind(new_name)
+            .bind(name)
+            .execute(&mut *transaction)
+            .await?;
+        sqlx::query("UPDATE friends SET level = ?1 WHERE level = ?2")
+            .bind(new_name)
+            .bind(name)
+            .execute(&mut *transaction)
+            .await?;
+    }
+    transaction.commit().await
+}
+
+async fn count_friends_with_level(name: &str) -> Result<i64, sqlx::Error> {
+    let pool = open_pool().await?;
+    let row = sqlx::query("SELECT COUNT(*) AS count FROM friends WHERE level = ?1")
+        .bind(name)
+        .fetch_one(&pool)
+        .await?;
+    Ok(row.get("count"))
+}
+
+async fn delete_level(name: &str) -> Result<bool, sqlx::Error> {
+    let pool = open_pool().await?;
+    let result = sqlx::query("DELETE FROM levels WHERE name = ?1")
+        .bind(name)
+        .execute(&pool)
+        .await?;
+    Ok(result.rows_affected() > 0)
+}
+
+/// Adds a closeness level with its base chance.
+pub async fn add_level(name: String, chance: f64) {
+    if !is_valid_chance(chance) {
+        println!("Chance must be a positive number");
+        return;
+    }
+
+    let config = match read_config().await {
+        Ok(config) => config,
+        Err(err) => {
+            eprintln!("Failed to read data: {err}");
+            return;
+        }
+    };
+    if utils::level_chance(&config, &name).is_some() {
+        println!("Level \"{name}\" already exists, please use a different name");
+        return;
+    }
+
+    if let Err(err) = insert_level(&name, chance).await {
+        eprintln!("Failed to write data: {err}");
+    }
+}
+
+/// Edits level name and/or base chance.
+pub async fn edit_level(name: String, new_name: Option<String>, new_chance: Option<f64>) {
+    if new_name.is_none() && new_chance.is_none() {
+        println!("No changes requested");
+        return;
+    }
+    if new_chance.is_some_and(|chance| !is_valid_chance(chance)) {
+        println!("Chance must be a positive number");
+        return;
+    }
+
+    let config = match read_config().await {
+        Ok(config) => config,
+        Err(err) => {
+            eprintln!("Failed to read data: {err}");
+            return;
+        }
+    };
+    if utils::level_chance(&config, &name).is_none() {
+        println!("Level \"{name}\" not found");
+        return;
+    }
+    if let Some(ref new_name_value) = new_name {
+        if *new_name_value != name && utils::level_chance(&config, new_name_value).is_some() {
+            println!("Level \"{new_name_value}\" already exists, please use a different name");
+            return;
+        }
+    }
+
+    if let Err(err) = update_level(&name, new_name.as_deref(), new_chance).await {
+        eprintln!("Failed to write data: {err}");
+        return;
+    }
+
+    // Base chances weigh every redistribution, so history has to be replayed.
+    if new_chance.is_some() {
+        if let Err(err) = rebuild_chances_from_memories().await {
+            eprintln!("Failed to rebuild chances: {err}");
+        }
+    }
+}
+
+/// Removes a level that no friend uses anymore.
+pub async fn remove_level(name: String) {
+    match count_friends_with_level(&name).await {
+        Ok(0) => {}
+        Ok(used_count) => {
+            println!("Level \"{name}\" is still used by {used_count} friend(s)");
+            return;
+        }
+        Err(err) => {
+            eprintln!("Failed to read data: {err}");
+            return;
+        }
+    }
+
+    match delete_level(&name).await {
+        Ok(true) => {}
+        Ok(false) => println!("Level \"{name}\" not found"),
+        Err(err) => eprintln!("Failed to write data: {err}"),
+    }
+}
+
+/// Lists levels sorted by base chance.
+pub async fn list_levels(format: OutputFormat) {
+    let config = match read_config().await {
+        Ok(config) => config,
+        Err(err) => {
+            eprintln!("Failed to read data: {err}");
+            return;
+        }
+    };
+
+    let rendered_list = match format {
+        OutputFormat::Text => utils::list_levels(&config),
+        _ => output::render(format, utils::sort_levels(&config)),
+    };
+    println!("{rendered_list}");
+}
@@ -0,0 +1,377 @@
+use sqlx::{QueryBuilder, Row, Sqlite};
+
+use super::{
+    group_memory_rows, memory_row, open_pool,
+    output::{self, OutputFormat},
+    read_config, rebuild_chances_from_memories, utils, MemoryInfo, MEMORY_COLUMNS,
+};
+
+/// A point in time given on the command line. Like stored timestamps, it is in UTC.
+#[derive(Clone, Debug, PartialEq)]
+pub enum DateInput {
+    /// `YYYY-MM-DD HH:MM:SS`
+    Absolute(String),
+    DaysAgo(u32),
+}
+
+impl DateInput {
+    /// Arguments of SQLite's `datetime(time_value, modifier)` for this point in time.
+    fn datetime_args(&self) -> (String, String) {
+        match self {
+            DateInput::Absolute(datetime) => (datetime.clone(), "+0 days".to_owned()),
+            DateInput::DaysAgo(days) => ("now".to_owned(), format!("-{days} days")),
+        }
+    }
+}
+
+fn is_valid_time(value: &str) -> bool {
+    let parts = value.split(':').collect::<Vec<&str>>();
+    if !(2..=3).contains(&parts.len()) || parts.iter().any(|part| part.len() != 2) {
+        return false;
+    }
+    parts
+        .iter()
+        .zip([24, 60, 60])
+        .all(|(part, bound)| part.parse::<u32>().is_ok_and(|value| value < bound))
+}
+
+/// Parses `YYYY-MM-DD`, `YYYY-MM-DD HH:MM[:SS]`, `today`, `yesterday` and
+/// `N days ago` / `N weeks ago`.
+pub fn parse_date_input(value: &str) -> Option<DateInput> {
+    let value = value.trim().to_lowercase();
+    match value.as_str() {
+        "today" | "now" => return Some(DateInput::DaysAgo(0)),
+        "yesterday" => return Some(DateInput::DaysAgo(1)),
+        _ => {}
+    }
+    if let Some(amount_and_unit) = value.strip_suffix(" ago") {
+        let (amount, unit) = amount_and_unit.split_once(' ')?;
+        let unit_days = match unit {
+            "day" | "days" => 1,
+            "week" | "weeks" => 7,
+            _ => return None,
+        };
+        return amount
+            .parse::<u32>()
+            .ok()?
+            .checked_mul(unit_days)
+            .map(DateInput::DaysAgo);
+    }
+
+    let (date, time) = match value.split_once([' ', 't']) {
+        Some((date, time)) => (date, time),
+        None => (value.as_str(), "00:00"),
+    };
+    if !utils::is_valid_date(date) || !is_valid_time(time) {
+        return None;
+    }
+    let seconds = if time.len() == 5 { ":00" } else { "" };
+    Some(DateInput::Absolute(format!("{date} {time}{seconds}")))
+}
+
+/// Resolves `date` to a stored timestamp, and whether it lies in the future.
+pub(super) async fn resolve_date(date: &DateInput) -> Result<(String, bool), sqlx::Error> {
+    let pool = open_pool().await?;
+    let (time_value, modifier) = date.datetime_args();
+    let row = sqlx::query(
+        "SELECT datetime(?1, ?2) AS created_at, datetime(?1, ?2) > datetime('now') AS in_future",
+    )
+    .bind(time_value)
+    .bind(modifier)
+    .fetch_one(&pool)
+    .await?;
+    Ok((row.get("created_at"), row.get("in_future")))
+}
+
+/// Free-form details of a memory.
+#[derive(Default)]
+pub struct MemoryDetails {
+    pub note: Option<String>,
+    pub place: Option<String>,
+    pub tags: Vec<String>,
+}
+
+/// Criteria for `memory list` and `memory search`; unset fields don't filter anything.
+#[derive(Default)]
+pub struct MemoryFilter {
+    /// Case-insensitive text to find in notes, places or tags.
+    pub text: Option<String>,
+    pub friend: Option<String>,
+    pub kind: Option<String>,
+    /// First day to include.
+    pub since: Option<DateInput>,
+    /// Last day to include.
+    pub until: Option<DateInput>,
+    pub limit: Option<i64>,
+    pub offset: Option<i64>,
+}
+
+/// Reads memories matching `filter`, newest first.
+async fn read_filtered_memories(filter: &MemoryFilter) -> Result<Vec<MemoryInfo>, sqlx::Error> {
+    let pool = open_pool().await?;
+    let mut query = QueryBuilder::<Sqlite>::new(format!(
+        "SELECT {MEMORY_COLUMNS}
+            FROM memories
+            LEFT JOIN memory_participants ON memory_participants.memory_id = memories.id
+            LEFT JOIN friends ON friends.id = memory_participants.friend_id
+            WHERE memories.id IN (SELECT id FROM memories WHERE 1 = 1"
+    ));
+    if let Some(ref text) = filter.text {
+        query
+            .push(" AND (instr(lower(COALESCE(note, '')), lower(")
+            .push_bind(text)
+            .push(")) > 0 OR instr(lower(COALESCE(place, '')), lower(")
+            .push_bind(text)
+            .push(
+                ")) > 0 OR EXISTS (SELECT 1 FROM memory_tags
+                    WHERE memory_tags.memory_id = memories.id AND instr(lower(tag), lower(",
+            )
+            .push_bind(text)
+            .push(")) > 0))");
+    }
+    if let Some(ref friend) = filter.friend {
+        query
+            .push(
+                " AND EXISTS (SELECT 1 FROM memory_participants
+                    JOIN friends ON friends.id = memory_participants.friend_id
+                    WHERE memory_participants.memory_id = memories.id AND friends.name = ",
+            )
+            .push_bind(friend)
+            .push(")");
+    }
+    if let Some(ref kind) = filter.kind {
+        query.push(" AND kind = ").push_bind(kind);
+    }
+    if let Some(ref since) = filter.since {
+        let (time_value, modifier) = since.datetime_args();
+        query
+            .push(" AND date(created_at) >= date(")
+            .push_bind(time_value)
+            .push(", ")
+            .push_bind(modifier)
+            .push(")");
+    }
+    if let Some(ref until) = filter.until {
+        let (time_value, modifier) = until.datetime_args();
+        query
+            .push(" AND date(created_at) <= date(")
+            .push_bind(time_value)
+            .push(", ")
+            .push_bind(modifier)
+            .push(")");
+    }
+    // A negative limit means no limit in SQLite.
+    query
+        .push(" ORDER BY created_at DESC, id DESC LIMIT ")
+        .push_bind(filter.limit.unwrap_or(-1))
+        .push(" OFFSET ")
+        .push_bind(filter.offset.unwrap_or(0))
+        .push(") ORDER BY memories.created_at DESC, memories.id DESC, friends.name ASC");
+
+    let rows = query.build().fetch_all(&pool).await?;
+    Ok(group_memory_rows(
+        rows.into_iter().map(memory_row).collect(),
+    ))
+}
+
+/// Changes to apply to a recorded memory; unset fields are kept.
+#[derive(Default)]
+pub struct MemoryEdit {
+    pub kind: Option<String>,
+    pub names: Option<Vec<String>>,
+    pub date: Option<DateInput>,
+    /// An empty note removes the current one.
+    pub note: Option<String>,
+}
+
+async fn update_memory(
+    id: i64,
+    kind: Option<&str>,
+    names: Option<&[String]>,
+    created_at: Option<&str>,
+    note: Option<&str>,
+) -> Result<bool, sqlx::Error> {
+    let pool = open_pool().await?;
+    let mut transaction = pool.begin().await?;
+    let result = sqlx::query(
+        "UPDATE memories SET
+            kind = COALESCE(?2, kind),
+            created_at = COALESCE(?3, created_at),
+            note = CASE WHEN ?4 IS NULL THEN note ELSE NULLIF(?4, '') END
+            WHERE id = ?1",
+    )
+    .bind(id)
+    .bind(kind)
+    .bind(created_at)
+    .bind(note)
+    .execute(&mut *transaction)
+    .await?;
+    if result.rows_affected() == 0 {
+        return Ok(false);
+    }
+
+    if let Some(names) = names {
+        sqlx::query("DELETE FROM memory_participants WHERE memory_id = ?1")
+            .bind(id)
+            .execute(&mut *transaction)
+            .await?;
+        for name in names {
+            sqlx::query(
+                "INSERT OR IGNORE INTO memory_participants (memory_id, friend_id)
+                    SELECT ?1, id FROM friends WHERE name = ?2",
+            )
+            .bind(id)
+            .bind(name)
+            .execute(&mut *transaction)
+            .await?;
+        }
+    }
+    transaction.commit().await?;
+    Ok(true)
+}
+
+/// Edits kind, participants, date and/or note of a memory, then replays history.
+pub async fn edit_memory(id: i64, edit: MemoryEdit) {
+    if edit.kind.is_none() && edit.names.is_none() && edit.date.is_none() && edit.note.is_none() {
+        println!("No changes requested");
+        return;
+    }
+    let names = edit.names.as_deref().map(utils::unique_names);
+    if names.as_ref().is_some_and(Vec::is_empty) {
+        println!("Please specify at least one name");
+        return;
+    }
+
+    let config = match read_config().await {
+        Ok(config) => config,
+        Err(err) => {
+            eprintln!("Failed to read data: {err}");
+            return;
+        }
+    };
+    if let Some(ref kind) = edit.kind {
+        if utils::kind_reduction(&config, kind).is_none() {
+            println!("Memory kind \"{kind}\" not found");
+            return;
+        }
+    }
+    if let Some(ref names) = names {
+        let unknown_names = utils::get_unknown_names(&config, names);
+        if !unknown_names.is_empty() {
+            let unknown_names_string = unknown_names
+                .iter()
+                .map(|&unknown_name| unknown_name.to_owned())
+                .collect::<Vec<String>>()
+                .join(", ");
+            println!("The following names are not added yet: {unknown_names_string}");
+            return;
+        }
+    }
+    let created_at = match edit.date {
+        Some(ref date) => match resolve_date(date).await {
+            Ok((_, true)) => {
+                println!("Memory date can't be in the future");
+                return;
+            }
+            Ok((created_at, false)) => Some(created_at),
+            Err(err) => {
+                eprintln!("Failed to read data: {err}");
+                return;
+            }
+        },
+        None => None,
+    };
+
+    match update_memory(
+        id,
+        edit.kind.as_deref(),
+        names.as_deref(),
+        created_at.as_deref(),
+        edit.note.as_deref(),
+    )
+    .await
+    {
+        Ok(true) => {}
+        Ok(false) => {
+            println!("Memory id {id} not found");
+            return;
+        }
+        Err(err) => {
+            eprintln!("Failed to write data: {err}");
+            return;
+        }
+    }
+
+    // Notes don't affect chances, everything else changes how history replays.
+    if edit.kind.is_some() || names.is_some() || created_at.is_some() {
+        if let Err(err) = rebuild_chances_from_memories().await {
+            eprintln!("Failed to rebuild chances: {err}");
+        }
+    }
+}
+
+/// Finds memories whose note, place or tags contain `text`, newest first.
+pub async fn search_memories(text: String, filter: MemoryFilter, format: OutputFormat) {
+    list_memories(
+        MemoryFilter {
+            text: Some(text),
+            ..filter
+        },
+        format,
+    )
+    .await
+}
+
+/// Lists recorded memories matching `filter`, newest first.
+pub async fn list_memories(filter: MemoryFilter, format: OutputFormat) {
+    let memories = match read_filtered_memories(&filter).await {
+        Ok(memories) => memories,
+        Err(err) => {
+            eprintln!("Failed to read memories: {err}");
+            return;
+        }
+    };
+
+    println!("{}", output::render(format, &memories));
+}
+
+#[cfg(test)]
+mod test {
+    use super::{is_valid_time, parse_date_input, DateInput};
+
+    #[test]
+    fn test_is_valid_time() {
+        assert!(is_valid_time("18:30"));
+        assert!(is_valid_time("00:00:59"));
+        assert!(!is_valid_time("24:00"));
+        assert!(!is_valid_time("8:30"));
+        assert!(!is_valid_time("18"));
+    }
+
+    #[test]
+    fn test_parse_date_input() {
+        assert_eq!(parse_date_input("today"), Some(DateInput::DaysAgo(0)));
+        assert_eq!(parse_date_input("Yesterday"), Some(DateInput::DaysAgo(1)));
+        assert_eq!(parse_date_input("3 days ago"), Some(DateInput::DaysAgo(3)));
+        assert_eq!(parse_date_input("1 day ago"), Some(DateInput::DaysAgo(1)));
+        assert_eq!(
+            parse_date_input("2 weeks ago"),
+            Some(DateInput::DaysAgo(14))
+        );
+        assert_eq!(
+            parse_date_input("2026-10-11"),
+            Some(DateInput::Absolute("2026-10-11 00:00:00".to_owned()))
+        );
+        assert_eq!(
+            parse_date_input("2026-10-11 18:30"),
+            Some(DateInput::Absolute("2026-10-11 18:30:00".to_owned()))
+        );
+        assert_eq!(
+            parse_date_input("2026-10-11T18:30:15"),
+            Some(DateInput::Absolute("2026-10-11 18:30:15".to_owned()))
+        );
+        assert_eq!(parse_date_input("3 months ago"), None);
+        assert_eq!(parse_date_input("2026-10-32"), None);
+        assert_eq!(parse_date_input("last weekend"), None);
+    }
+}
@@ -0,0 +1,414 @@
+use sqlx::{Row, SqlitePool};
+use std::{fs, path::PathBuf};
+
+use super::{connect, db_path};
+
+struct Migration {
+    version: i64,
+    description: &'static str,
+    statements: &'static [&'static str],
+}
+
+/// Forward-only schema migrations, applied in order. Never edit a released one.
+const MIGRATIONS: &[Migration] = &[
+    Migration {
+        version: 1,
+        description: "Create friends and memories tables",
+        statements: &[
+            "CREATE TABLE IF NOT EXISTS friends (
+                name TEXT PRIMARY KEY,
+                chance REAL NOT NULL,
+                level TEXT NOT NULL
+            )",
+            "CREATE TABLE IF NOT EXISTS memories (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                kind TEXT NOT NULL,
+                names TEXT NOT NULL,
+                created_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP
+            )",
+        ],
+    },
+    Migration {
+        version: 2,
+        description: "Add closeness levels",
+        statements: &[
+            "CREATE TABLE IF NOT EXISTS levels (
+                name TEXT PRIMARY KEY,
+                chance REAL NOT NULL
+            )",
+            "INSERT OR IGNORE INTO levels (name, chance)
+                VALUES ('aji', 50.0), ('ki', 5.0), ('chi', 1.0)",
+        ],
+    },
+    Migration {
+        version: 3,
+        description: "Add memory kinds",
+        statements: &[
+            "CREATE TABLE IF NOT EXISTS memory_kinds (
+                name TEXT PRIMARY KEY,
+                reduction REAL NOT NULL
+            )",
+            "INSERT OR IGNORE INTO memory_kinds (name, reduction)
+                VALUES ('hangout', 2.0), ('video-call', 1.0), ('call', 0.5), ('text', 0.25)",
+        ],
+    },
+    Migration {
+        version: 4,
+        description: "Add settings",
+        statements: &["CREATE TABLE IF NOT EXISTS settings (
+            key TEXT PRIMARY KEY,
+            value TEXT NOT NULL
+        )"],
+    },
+    Migration {
+        version: 5,
+        description: "Add friend ids and memory participants",
+        statements: &[
+            "CREATE TABLE friends_with_ids (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                name TEXT NOT NULL UNIQUE,
+                chance REAL NOT NULL,
+                level TEXT NOT NULL
+            )",
+            "INSERT INTO friends_with_ids (name, chance, level)
+                SELECT name, chance, level FROM friends ORDER BY rowid",
+            "DROP TABLE friends",
+            "ALTER TABLE friends_with_ids RENAME TO friends",
+            "CREATE TABLE memory_participants (
+                memory_id INTEGER NOT NULL REFERENCES memories(id) ON DELETE CASCADE,
+                friend_id INTEGER NOT NULL REFERENCES friends(id) ON DELETE CASCADE,
+                PRIMARY KEY (memory_id, friend_id)
+            )",
+            // Splits the newline-joined `names` column; names of friends that no longer
+            // exist have nothing to reference and are dropped, see `MIGRATION_WARNINGS`.
+            "WITH RECURSIVE split_names(memory_id, name, rest) AS (
+                SELECT id, '', names || char(10) FROM memories
+                UNION ALL
+                SELECT
+                    memory_id,
+                    substr(rest, 1, instr(rest, char(10)) - 1),
+                    substr(rest, instr(rest, char(10)) + 1)
+                FROM split_names
+                WHERE rest <> ''
+            )
+            INSERT OR IGNORE INTO memory_participants (memory_id, friend_id)
+                SELECT split_names.memory_id, friends.id
+                FROM split_names
+                JOIN friends ON friends.name = split_names.name",
+            "ALTER TABLE memories DROP COLUMN names",
+        ],
+    },
+    Migration {
+        version: 6,
+        description: "Add connection constraints",
+        statements: &[
+            "CREATE TABLE friend_constraints (
+                friend_id INTEGER PRIMARY KEY REFERENCES friends(id) ON DELETE CASCADE,
+                cooldown_days INTEGER,
+                interval_days INTEGER
+            )",
+            "CREATE TABLE level_constraints (
+                level TEXT PRIMARY KEY
+                    REFERENCES levels(name) ON UPDATE CASCADE ON DELETE CASCADE,
+                cooldown_days INTEGER,
+                interval_days INTEGER
+            )",
+        ],
+    },
+    Migration {
+        version: 7,
+        description: "Add memory notes, places and tags",
+        statements: &[
+            "ALTER TABLE memories ADD COLUMN note TEXT",
+            "ALTER TABLE memories ADD COLUMN place TEXT",
+            "CREATE TABLE memory_tags (
+                memory_id INTEGER NOT NULL REFERENCES memories(id) ON DELETE CASCADE,
+                tag TEXT NOT NULL,
+                PRIMARY KEY (memory_id, tag)
+            )",
+        ],
+    },
+    Migration {
+        version: 8,
+        description: "Add memory trash",
+        statements: &[
+            "CREATE TABLE memory_trash (
+                position INTEGER PRIMARY KEY AUTOINCREMENT,
+                memory_id INTEGER NOT NULL UNIQUE,
+                kind TEXT NOT NULL,
+                created_at TEXT NOT NULL,
+                note TEXT,
+                place TEXT,
+                trashed_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP
+            )",
+            "CREATE TABLE memory_trash_participants (
+                memory_id INTEGER NOT NULL
+                    REFERENCES memory_trash(memory_id) ON DELETE CASCADE,
+                friend_id INTEGER NOT NULL REFERENCES friends(id) ON DELETE CASCADE,
+                PRIMARY KEY (memory_id, friend_id)
+            )",
+            "CREATE TABLE memory_trash_tags (
+                memory_id INTEGER NOT NULL
+                    REFERENCES memory_trash(memory_id) ON DELETE CASCADE,
+                tag TEXT NOT NULL,
+                PRIMARY KEY (memory_id, tag)
+            )",
+        ],
+    },
+    Migration {
+        version: 9,
+        description: "Add friend notes and tags",
+        statements: &[
+            "ALTER TABLE friends ADD COLUMN note TEXT",
+            "CREATE TABLE friend_tags (
+                friend_id INTEGER NOT NULL REFERENCES friends(id) ON DELETE CASCADE,
+                tag TEXT NOT NULL,
+                PRIMARY KEY (friend_id, tag)
+            )",
+        ],
+    },
+    Migration {
+        version: 10,
+        description: "Add plans",
+        statements: &[
+            "CREATE TABLE plans (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                kind TEXT NOT NULL,
+                planned_on TEXT NOT NULL,
+                note TEXT,
+                created_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP
+            )",
+            "CREATE TABLE plan_participants (
+                plan_id INTEGER NOT NULL REFERENCES plans(id) ON DELETE CASCADE,
+                friend_id INTEGER NOT NULL REFERENCES friends(id) ON DELETE CASCADE,
+                PRIMARY KEY (plan_id, friend_id)
+            )",
+        ],
+    },
+    Migration {
+        version: 11,
+        description: "Add suggestion outcomes and snoozes",
+        statements: &[
+            "CREATE TABLE suggestions (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                friend_id INTEGER NOT NULL REFERENCES friends(id) ON DELETE CASCADE,
+                suggested_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+                outcome TEXT
+            )",
+            "CREATE TABLE snoozes (
+                friend_id INTEGER PRIMARY KEY REFERENCES friends(id) ON DELETE CASCADE,
+                until TEXT NOT NULL
+            )",
+        ],
+    },
+    Migration {
+        version: 12,
+        description: "Add suggestion probabilities",
+        statements: &["ALTER TABLE suggestions ADD COLUMN probability REAL"],
+    },
+];
+
+/// Queries run before the migration of the same version, each returning a `message` for
+/// every piece of data the migration can't carry over.
+const MIGRATION_WARNINGS: &[(i64, &str)] = &[(
+    5,
+    "WITH RECURSIVE split_names(memory_id, name, rest) AS (
+        SELECT id, '', names || char(10) FROM memories
+        UNION ALL
+        SELECT
+            memory_id,
+            substr(rest, 1, instr(rest, char(10)) - 1),
+            substr(rest, instr(rest, char(10)) + 1)
+        FROM split_names
+        WHERE rest <> ''
+    )
+    SELECT 'Memory ' || memory_id || ': \"' || name
+            || '\" is no longer a friend and was removed from its participants' AS message
+        FROM split_names
+        WHERE name <> '' AND NOT EXISTS (SELECT 1 FROM friends WHERE friends.name = split_names.name)
+        ORDER BY memory_id ASC",
+)];
+
+fn latest_version() -> i64 {
+    MIGRATIONS.last().map_or(0, |migration| migration.version)
+}
+
+fn backup_path(version: i64) -> PathBuf {
+    let mut path = db_path();
+    let file_name = path
+        .file_name()
+        .map(|file_name| file_name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    path.set_file_name(format!("{file_name}.v{version}.bak"));
+    path
+}
+
+async fn ensure_version_table(pool: &SqlitePool) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        "CREATE TABLE IF NOT EXISTS schema_version (
+            version INTEGER PRIMARY KEY,
+            applied_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP
+        )",
+    )
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+async fn current_version(pool: &SqlitePool) -> Result<i64, sqlx::Error> {
+    let row = sqlx::query("SELECT COALESCE(MAX(version), 0) AS version FROM schema_version")
+        .fetch_one(pool)
+        .await?;
+    Ok(row.get("version"))
+}
+
+async fn has_user_tables(pool: &SqlitePool) -> Result<bool, sqlx::Error> {
+    let row = sqlx::query(
+        "SELECT COUNT(*) AS count FROM sqlite_master
+            WHERE type = 'table' AND name NOT IN ('schema_version', 'sqlite_sequence')",
+    )
+    .fetch_one(pool)
+    .await?;
+    Ok(row.get::<i64, _>("count") > 0)
+}
+
+async fn backup_database(pool: &SqlitePool, version: i64) -> Result<PathBuf, sqlx::Error> {
+    let path = backup_path(version);
+    // `VACUUM INTO` refuses to overwrite, and an older backup of the same version is redundant.
+    let _ = fs::remove_file(&path);
+    sqlx::query("VACUUM INTO ?1")
+        .bind(path.to_string_lossy().into_owned())
+        .execute(pool)
+        .await?;
+    Ok(path)
+}
+
+/// Applies pending migrations, backing up the database first if it has data.
+/// Returns the versions before and after migrating.
+pub(super) async fn migrate(pool: &SqlitePool) -> Result<(i64, i64), sqlx::Error> {
+    ensure_version_table(pool).await?;
+    let from_version = current_version(pool).await?;
+    if from_version >= latest_version() {
+        return Ok((from_version, from_version));
+    }
+
+    if has_user_tables(pool).await? {
+        let path = backup_database(pool, from_version).await?;
+        eprintln!(
+            "Migrating database from version {from_version} to {}, backup saved to {}",
+            latest_version(),
+            path.display()
+        );
+    }
+
+    for migration in MIGRATIONS
+        .iter()
+        .filter(|migration| migration.version > from_version)
+    {
+        let mut transaction = pool.begin().await?;
+        for (_, query) in MIGRATION_WARNINGS
+            .iter()
+            .filter(|(version, _)| *version == migration.version)
+        {
+            for row in sqlx::query(query).fetch_all(&mut *transaction).await? {
+                eprintln!("{}", row.get::<String, _>("message"));
+            }
+        }
+        for statement in migration.statements {
+            sqlx::query(statement).execute(&mut *transaction).await?;
+        }
+        sqlx::query("INSERT INTO schema_version (version) VALUES (?1)")
+            .bind(migration.version)
+            .execute(&mut *transaction)
+            .await?;
+        transaction.commit().await?;
+    }
+
+    Ok((from_version, latest_version()))
+}
+
+async fn read_applied_migrations(pool: &SqlitePool) -> Result<Vec<(i64, String)>, sqlx::Error> {
+    ensure_version_table(pool).await?;
+    let rows = sqlx::query("SELECT version, applied_at FROM schema_version ORDER BY version ASC")
+        .fetch_all(pool)
+        .await?;
+    let applied = rows
+        .into_iter()
+        .map(|row| (row.get("version"), row.get("applied_at")))
+        .collect();
+    Ok(applied)
+}
+
+/// Applies pending migrations and reports the resulting schema version.
+pub async fn migrate_database() {
+    let pool = match connect().await {
+        Ok(pool) => pool,
+        Err(err) => {
+            eprintln!("Failed to open database: {err}");
+            return;
+        }
+    };
+
+    match migrate(&pool).await {
+        Ok((from_version, to_version)) if from_version == to_version => {
+            println!("Database is up to date (version {to_version})")
+        }
+        Ok((from_version, to_version)) => {
+            println!("Migrated database from version {from_version} to {to_version}")
+        }
+        Err(err) => eprintln!("Failed to migrate database: {err}"),
+    }
+}
+
+/// Prints applied and pending migrations without applying anything.
+pub async fn print_migration_status() {
+    let pool = match connect().await {
+        Ok(pool) => pool,
+        Err(err) => {
+            eprintln!("Failed to open database: {err}");
+            return;
+        }
+    };
+    let applied = match read_applied_migrations(&pool).await {
+        Ok(applied) => applied,
+        Err(err) => {
+            eprintln!("Failed to read data: {err}");
+            return;
+        }
+    };
+
+    let current = applied.last().map_or(0, |(version, _)| *version);
+    println!("Current version: {current} (latest: {})", latest_version());
+    for migration in MIGRATIONS {
+        let status = applied
+            .iter()
+            .find(|(version, _)| *version == migration.version)
+            .map_or("pending".to_owned(), |(_, applied_at)| {
+                format!("applied at {applied_at}")
+            });
+        println!(
+            "{:>3}  {}  ({status})",
+            migration.version, migration.description
+        );
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{MIGRATIONS, MIGRATION_WARNINGS};
+
+    #[test]
+    fn test_migrations_are_ordered() {
+        assert!(MIGRATIONS
+            .windows(2)
+            .all(|pair| pair[0].version + 1 == pair[1].version));
+        assert_eq!(MIGRATIONS[0].version, 1);
+    }
+
+    #[test]
+    fn test_migration_warnings_have_migrations() {
+        assert!(MIGRATION_WARNINGS.iter().all(|(version, _)| MIGRATIONS
+            .iter()
+            .any(|migration| migration.version == *version)));
+    }
+}
@@ -1,11 +1,61 @@
-use rand::distr::{weighted::WeightedIndex, Distribution};
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use serde::{Deserialize, Serialize};
 use sqlx::{
-    sqlite::{SqliteConnectOptions, SqlitePoolOptions},
+    sqlite::{SqliteConnectOptions, SqlitePoolOptions, SqliteRow},
     Row, SqlitePool,
 };
-use std::{fs, path::PathBuf};
+use std::{
+    collections::{HashMap, HashSet},
+    fs,
+    path::PathBuf,
+};
 
+mod constraint;
+mod content_line;
+mod feedback;
+mod friend_import;
+mod ics;
+mod kind;
+mod level;
+mod memory;
+mod migration;
+mod output;
+mod plan;
+mod report;
+mod settings;
+mod status;
+mod strategy;
+mod transfer;
+mod trash;
 mod utils;
+mod vcard;
+
+pub use constraint::{clear_constraint, set_constraint, show_constraints, ConstraintTarget};
+pub use feedback::{list_suggestion_history, suggest_interactively};
+pub use friend_import::import_friends_csv;
+pub use ics::import_ics;
+pub use kind::{add_kind, edit_kind, list_kinds, remove_kind};
+pub use level::{add_level, edit_level, list_levels, remove_level};
+pub use memory::{
+    edit_memory, list_memories, parse_date_input, search_memories, DateInput, MemoryDetails,
+    MemoryEdit, MemoryFilter,
+};
+pub use migration::{migrate_database, print_migration_status};
+pub use output::OutputFormat;
+pub use plan::{
+    add_plan, complete_plan, export_plans_ics, list_plans, parse_plan_date, remove_plan,
+};
+pub use report::print_fairness_report;
+pub use settings::{reset_defaults, set_default, show_defaults};
+pub use status::print_status;
+pub use strategy::StrategyKind;
+pub use transfer::{export_data, import_data, ImportMode};
+pub use trash::{list_trash, purge_trash, redo_memory};
+pub use vcard::{export_friends_vcard, import_friends_vcard};
+
+use settings::{ChanceModel, Settings};
+use strategy::SuggestionStrategy;
 
 mod default_chance {
     pub const AJI: f64 = 50.0;
@@ -20,25 +70,121 @@ mod default_reduction {
     pub const TEXT: f64 = 0.25;
 }
 
+/// Levels created by the migrations, which `akc friend <level>` must be able to reach.
+pub const DEFAULT_LEVELS: [(&str, f64); 3] = [
+    ("aji", default_chance::AJI),
+    ("ki", default_chance::KI),
+    ("chi", default_chance::CHI),
+];
+
+/// Memory kinds created by the migrations, which `akc memory <kind>` must be able to reach.
+pub const DEFAULT_KINDS: [(&str, f64); 4] = [
+    ("hangout", default_reduction::HANGOUT),
+    ("video-call", default_reduction::VIDEO_CALL),
+    ("call", default_reduction::CALL),
+    ("text", default_reduction::TEXT),
+];
+
 const APP_DIR_NAME: &str = "akc";
 const DB_FILE_NAME: &str = "akc.db";
-const MEMORY_NAMES_SEPARATOR: &str = "\n";
 
+#[derive(Serialize)]
 pub struct FriendInfo {
+    #[serde(skip)]
+    id: Option<i64>,
     name: String,
     chance: f64,
     level: String,
 }
 
+/// Builds a friend who isn't stored, for tests.
+#[cfg(test)]
+fn friend_info(name: &str, level: &str, chance: f64) -> FriendInfo {
+    FriendInfo {
+        id: None,
+        name: name.to_owned(),
+        chance,
+        level: level.to_owned(),
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct LevelInfo {
+    name: String,
+    chance: f64,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct KindInfo {
+    name: String,
+    reduction: f64,
+}
+
+#[derive(Serialize)]
 struct MemoryInfo {
     id: i64,
     kind: String,
+    #[serde(rename = "participants")]
     names: Vec<String>,
+    created_at: String,
+    note: Option<String>,
+    place: Option<String>,
+    tags: Vec<String>,
+}
+
+/// A suggested friend with the probability they had of being picked.
+#[derive(Serialize)]
+struct Suggestion {
+    name: String,
+    level: String,
+    chance: f64,
+    /// Percentage the friend had of being drawn from their candidates before anything was
+    /// drawn.
+    probability: f64,
+}
+
+/// Restricts which friends may be suggested; unset fields don't filter anything.
+#[derive(Default)]
+pub struct SuggestFilter {
+    pub level: Option<String>,
+    /// A friend tag.
+    pub tag: Option<String>,
+    pub exclude: Vec<String>,
+    /// A memory kind whose reduction the friend's chance has to cover.
+    pub for_kind: Option<String>,
+}
+
+/// What `suggest` should draw.
+pub struct SuggestOptions {
+    /// How many distinct friends to suggest.
+    pub count: usize,
+    /// Only suggest friends who have all shared a memory with each other.
+    pub group: bool,
+    pub filter: SuggestFilter,
+    /// Strategy to use instead of the default one.
+    pub strategy: Option<StrategyKind>,
+    /// Softmax temperature to use instead of the default one.
+    pub temperature: Option<f64>,
+}
+
+impl Default for SuggestOptions {
+    fn default() -> Self {
+        SuggestOptions {
+            count: 1,
+            group: false,
+            filter: SuggestFilter::default(),
+            strategy: None,
+            temperature: None,
+        }
+    }
 }
 
 #[derive(Default)]
 pub struct AkcConfig {
     friends: Vec<FriendInfo>,
+    levels: Vec<LevelInfo>,
+    kinds: Vec<KindInfo>,
+    settings: Settings,
 }
 
 fn db_path() -> PathBuf {
@@ -59,87 +205,209 @@ pub fn print_db_path() {
     println!("{}", db_path().display());
 }
 
-async fn open_pool() -> Result<SqlitePool, sqlx::Error> {
+async fn connect() -> Result<SqlitePool, sqlx::Error> {
     let options = SqliteConnectOptions::new()
         .filename(db_path())
         .create_if_missing(true);
-    let pool = SqlitePoolOptions::new()
+    SqlitePoolOptions::new()
         .max_connections(1)
         .connect_with(options)
-        .await?;
-    init_schema(&pool).await?;
-    Ok(pool)
+        .await
 }
 
-async fn init_schema(pool: &SqlitePool) -> Result<(), sqlx::Error> {
-    sqlx::query(
-        "CREATE TABLE IF NOT EXISTS friends (
-            name TEXT PRIMARY KEY,
-            chance REAL NOT NULL,
-            level TEXT NOT NULL
-        )",
-    )
-    .execute(pool)
-    .await?;
-    sqlx::query(
-        "CREATE TABLE IF NOT EXISTS memories (
-            id INTEGER PRIMARY KEY AUTOINCREMENT,
-            kind TEXT NOT NULL,
-            names TEXT NOT NULL,
-            created_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP
-        )",
-    )
-    .execute(pool)
-    .await?;
-    Ok(())
+async fn open_pool() -> Result<SqlitePool, sqlx::Error> {
+    let pool = connect().await?;
+    migration::migrate(&pool).await?;
+    Ok(pool)
 }
 
 async fn read_config() -> Result<AkcConfig, sqlx::Error> {
     let pool = open_pool().await?;
-    let rows = sqlx::query("SELECT name, chance, level FROM friends")
+    let rows = sqlx::query("SELECT id, name, chance, level FROM friends")
         .fetch_all(&pool)
         .await?;
     let friends = rows
         .into_iter()
         .map(|row| FriendInfo {
+            id: Some(row.get("id")),
             name: row.get("name"),
             chance: row.get("chance"),
             level: row.get("level"),
         })
         .collect();
-    Ok(AkcConfig { friends })
+    let rows = sqlx::query("SELECT name, chance FROM levels")
+        .fetch_all(&pool)
+        .await?;
+    let levels = rows
+        .into_iter()
+        .map(|row| LevelInfo {
+            name: row.get("name"),
+            chance: row.get("chance"),
+        })
+        .collect();
+    let rows = sqlx::query("SELECT name, reduction FROM memory_kinds")
+        .fetch_all(&pool)
+        .await?;
+    let kinds = rows
+        .into_iter()
+        .map(|row| KindInfo {
+            name: row.get("name"),
+            reduction: row.get("reduction"),
+        })
+        .collect();
+    let settings = settings::read_settings(&pool).await?;
+    Ok(AkcConfig {
+        friends,
+        levels,
+        kinds,
+        settings,
+    })
+}
+
+/// Memory columns read by `memory_row`, selected from `memories` joined with the
+/// participating `friends`.
+const MEMORY_COLUMNS: &str = "memories.id, memories.kind, memories.created_at, memories.note,
+    memories.place,
+    (SELECT group_concat(tag, char(10)) FROM memory_tags
+        WHERE memory_tags.memory_id = memories.id) AS tags,
+    friends.name";
+
+/// Reads a memory and one of its participants from a row of `MEMORY_COLUMNS`.
+fn memory_row(row: SqliteRow) -> (MemoryInfo, Option<String>) {
+    let tags: Option<String> = row.get("tags");
+    let mut tags = tags
+        .map(|tags| tags.lines().map(str::to_owned).collect::<Vec<String>>())
+        .unwrap_or_default();
+    tags.sort();
+    let memory = MemoryInfo {
+        id: row.get("id"),
+        kind: row.get("kind"),
+        names: Vec::new(),
+        created_at: row.get("created_at"),
+        note: row.get("note"),
+        place: row.get("place"),
+        tags,
+    };
+    (memory, row.get("name"))
 }
 
 async fn read_memories() -> Result<Vec<MemoryInfo>, sqlx::Error> {
     let pool = open_pool().await?;
-    let rows = sqlx::query("SELECT id, kind, names FROM memories ORDER BY id ASC")
-        .fetch_all(&pool)
-        .await?;
-    let memories = rows
+    let rows = sqlx::query(&format!(
+        "SELECT {MEMORY_COLUMNS}
+            FROM memories
+            LEFT JOIN memory_participants ON memory_participants.memory_id = memories.id
+            LEFT JOIN friends ON friends.id = memory_participants.friend_id
+            ORDER BY memories.created_at ASC, memories.id ASC, friends.name ASC"
+    ))
+    .fetch_all(&pool)
+    .await?;
+    Ok(group_memory_rows(
+        rows.into_iter().map(memory_row).collect(),
+    ))
+}
+
+/// Reads how many days have passed since the latest memory with each friend.
+async fn read_days_since_last_memory() -> Result<HashMap<String, f64>, sqlx::Error> {
+    let pool = open_pool().await?;
+    let rows = sqlx::query(
+        "SELECT friends.name, julianday('now') - julianday(MAX(memories.created_at)) AS days
+            FROM friends
+            JOIN memory_participants ON memory_participants.friend_id = friends.id
+            JOIN memories ON memories.id = memory_participants.memory_id
+            GROUP BY friends.id",
+    )
+    .fetch_all(&pool)
+    .await?;
+    let days_since_last_memory = rows
         .into_iter()
-        .map(|row| MemoryInfo {
-            id: row.get("id"),
-            kind: row.get("kind"),
-            names: deserialize_memory_names(&row.get::<String, _>("names")),
-        })
+        .map(|row| (row.get("name"), row.get("days")))
         .collect();
-    Ok(memories)
+    Ok(days_since_last_memory)
+}
+
+/// Reads the names of friends with the given tag.
+async fn read_tagged_friend_names(tag: &str) -> Result<HashSet<String>, sqlx::Error> {
+    let pool = open_pool().await?;
+    let rows = sqlx::query(
+        "SELECT friends.name FROM friends
+            JOIN friend_tags ON friend_tags.friend_id = friends.id
+            WHERE friend_tags.tag = ?1",
+    )
+    .bind(tag)
+    .fetch_all(&pool)
+    .await?;
+    Ok(rows.into_iter().map(|row| row.get("name")).collect())
 }
 
+/// Reads, for each friend, the friends they have shared at least one memory with.
+async fn read_companions() -> Result<HashMap<String, HashSet<String>>, sqlx::Error> {
+    let pool = open_pool().await?;
+    let rows = sqlx::query(
+        "SELECT DISTINCT friend.name, companion.name AS companion
+            FROM memory_participants
+            JOIN memory_participants AS companion_participants
+                ON companion_participants.memory_id = memory_participants.memory_id
+                AND companion_participants.friend_id != memory_participants.friend_id
+            JOIN friends AS friend ON friend.id = memory_participants.friend_id
+            JOIN friends AS companion ON companion.id = companion_participants.friend_id",
+    )
+    .fetch_all(&pool)
+    .await?;
+    let mut companions: HashMap<String, HashSet<String>> = HashMap::new();
+    for row in rows {
+        companions
+            .entry(row.get("name"))
+            .or_default()
+            .insert(row.get("companion"));
+    }
+    Ok(companions)
+}
+
+/// Folds `(memory, participant)` rows, grouped by memory id, into one `MemoryInfo` per
+/// memory.
+fn group_memory_rows(rows: Vec<(MemoryInfo, Option<String>)>) -> Vec<MemoryInfo> {
+    let mut memories: Vec<MemoryInfo> = Vec::new();
+    for (memory, name) in rows {
+        match memories.last_mut() {
+            Some(last_memory) if last_memory.id == memory.id => last_memory.names.extend(name),
+            _ => memories.push(MemoryInfo {
+                names: name.into_iter().collect(),
+                ..memory
+            }),
+        }
+    }
+    memories
+}
+
+/// Persists names, levels and chances of the friends in `config`.
+///
+/// Friends without an id yet are inserted; removed friends are deleted separately so
+/// that their memory participations are cascaded.
 async fn write_config(config: &AkcConfig) -> Result<(), sqlx::Error> {
     let pool = open_pool().await?;
     let mut transaction = pool.begin().await?;
-    sqlx::query("DELETE FROM friends")
-        .execute(&mut *transaction)
-        .await?;
 
     for friend in &config.friends {
-        sqlx::query("INSERT INTO friends (name, chance, level) VALUES (?1, ?2, ?3)")
-            .bind(&friend.name)
-            .bind(friend.chance)
-            .bind(&friend.level)
-            .execute(&mut *transaction)
-            .await?;
+        match friend.id {
+            Some(id) => {
+                sqlx::query("UPDATE friends SET name = ?1, chance = ?2, level = ?3 WHERE id = ?4")
+                    .bind(&friend.name)
+                    .bind(friend.chance)
+                    .bind(&friend.level)
+                    .bind(id)
+                    .execute(&mut *transaction)
+                    .await?;
+            }
+            None => {
+                sqlx::query("INSERT INTO friends (name, chance, level) VALUES (?1, ?2, ?3)")
+                    .bind(&friend.name)
+                    .bind(friend.chance)
+                    .bind(&friend.level)
+                    .execute(&mut *transaction)
+                    .await?;
+            }
+        }
     }
 
     transaction.commit().await?;
@@ -154,38 +422,81 @@ fn get_unit_added_chance(total_reduction: f64, current_total_chance: f64) -> f64
     }
 }
 
-fn serialize_memory_names(names: &[String]) -> String {
-    names.join(MEMORY_NAMES_SEPARATOR)
-}
-
-fn deserialize_memory_names(names: &str) -> Vec<String> {
-    if names.is_empty() {
-        return Vec::new();
+/// Saves a memory, dated now unless `created_at` is given.
+async fn save_memory(
+    kind: &str,
+    names: &[String],
+    created_at: Option<&str>,
+    details: &MemoryDetails,
+) -> Result<(), sqlx::Error> {
+    let pool = open_pool().await?;
+    let mut transaction = pool.begin().await?;
+    let memory_id = sqlx::query(
+        "INSERT INTO memories (kind, created_at, note, place)
+            VALUES (?1, COALESCE(?2, CURRENT_TIMESTAMP), ?3, ?4)",
+    )
+    .bind(kind)
+    .bind(created_at)
+    .bind(&details.note)
+    .bind(&details.place)
+    .execute(&mut *transaction)
+    .await?
+    .last_insert_rowid();
+    for tag in &details.tags {
+        sqlx::query("INSERT OR IGNORE INTO memory_tags (memory_id, tag) VALUES (?1, ?2)")
+            .bind(memory_id)
+            .bind(tag)
+            .execute(&mut *transaction)
+            .await?;
     }
-    names
-        .split(MEMORY_NAMES_SEPARATOR)
-        .map(ToOwned::to_owned)
-        .collect()
+    for name in names {
+        sqlx::query(
+            "INSERT OR IGNORE INTO memory_participants (memory_id, friend_id)
+                SELECT ?1, id FROM friends WHERE name = ?2",
+        )
+        .bind(memory_id)
+        .bind(name)
+        .execute(&mut *transaction)
+        .await?;
+    }
+    transaction.commit().await
 }
 
-fn reduction_from_kind(kind: &str) -> Option<f64> {
-    match kind {
-        "hangout" => Some(default_reduction::HANGOUT),
-        "video-call" => Some(default_reduction::VIDEO_CALL),
-        "call" => Some(default_reduction::CALL),
-        "text" => Some(default_reduction::TEXT),
-        _ => None,
-    }
+/// Counts the memories and undone memories `name` takes part in.
+async fn count_friend_memories(name: &str) -> Result<(i64, i64), sqlx::Error> {
+    let pool = open_pool().await?;
+    let row = sqlx::query(
+        "SELECT
+            (SELECT COUNT(*) FROM memory_participants
+                WHERE memory_participants.friend_id = friends.id) AS memory_count,
+            (SELECT COUNT(*) FROM memory_trash_participants
+                WHERE memory_trash_participants.friend_id = friends.id) AS trashed_count
+            FROM friends WHERE name = ?1",
+    )
+    .bind(name)
+    .fetch_optional(&pool)
+    .await?;
+    Ok(row.map_or((0, 0), |row| {
+        (row.get("memory_count"), row.get("trashed_count"))
+    }))
 }
 
-async fn save_memory(kind: &str, names: &[String]) -> Result<(), sqlx::Error> {
+async fn delete_friend(name: &str) -> Result<bool, sqlx::Error> {
     let pool = open_pool().await?;
-    sqlx::query("INSERT INTO memories (kind, names) VALUES (?1, ?2)")
-        .bind(kind)
-        .bind(serialize_memory_names(names))
-        .execute(&pool)
+    let mut transaction = pool.begin().await?;
+    let result = sqlx::query("DELETE FROM friends WHERE name = ?1")
+        .bind(name)
+        .execute(&mut *transaction)
         .await?;
-    Ok(())
+    // Plan participants are cascaded, so plans with nobody left to meet are dropped.
+    sqlx::query(
+        "DELETE FROM plans
+            WHERE NOT EXISTS (SELECT 1 FROM plan_participants WHERE plan_id = plans.id)",
+    )
+    .execute(&mut *transaction)
+    .await?;
+    transaction.commit().await?;
+    Ok(result.rows_affected() > 0)
 }
 
 async fn delete_memory(id: i64) -> Result<bool, sqlx::Error> {
@@ -198,13 +509,18 @@ async fn delete_memory(id: i64) -> Result<bool, sqlx::Error> {
 }
 
 fn reset_chances_to_level_defaults(config: &mut AkcConfig) {
-    for friend in &mut config.friends {
-        friend.chance = level_default_chance(&friend.level).unwrap_or(0.0);
+    let default_chances = config
+        .friends
+        .iter()
+        .map(|friend| utils::level_chance(config, &friend.level).unwrap_or(0.0))
+        .collect::<Vec<f64>>();
+    for (friend, chance) in config.friends.iter_mut().zip(default_chances) {
+        friend.chance = chance;
     }
 }
 
 fn apply_memory_to_config(config: &mut AkcConfig, kind: &str, names: &[String]) {
-    let Some(reduction) = reduction_from_kind(kind) else {
+    let Some(reduction) = utils::kind_reduction(config, kind) else {
         return;
     };
     if names.is_empty() {
@@ -234,24 +550,17 @@ async fn rebuild_chances_from_memories() -> Result<(), sqlx::Error> {
     write_config(&config).await
 }
 
-fn level_default_chance(level: &str) -> Option<f64> {
-    match level {
-        "aji" => Some(default_chance::AJI),
-        "ki" => Some(default_chance::KI),
-        "chi" => Some(default_chance::CHI),
-        _ => None,
-    }
-}
-
-fn make_friend(name: String, level: &str) -> Option<FriendInfo> {
-    level_default_chance(level).map(|chance| FriendInfo {
+fn make_friend(config: &AkcConfig, name: String, level: &str) -> Option<FriendInfo> {
+    utils::level_chance(config, level).map(|chance| FriendInfo {
+        id: None,
         name,
         chance,
         level: level.to_owned(),
     })
 }
 
-async fn add_friend(friend_info: FriendInfo) {
+/// Adds a friend with the given level.
+pub async fn add_friend(level: String, name: String) {
     let mut config = match read_config().await {
         Ok(config) => config,
         Err(err) => {
@@ -259,6 +568,10 @@ async fn add_friend(friend_info: FriendInfo) {
             return;
         }
     };
+    let Some(friend_info) = make_friend(&config, name, &level) else {
+        println!("Level \"{level}\" not found");
+        return;
+    };
     let is_duplicate = utils::is_name_duplicate(&config, &friend_info.name);
 
     if is_duplicate {
@@ -274,25 +587,43 @@ async fn add_friend(friend_info: FriendInfo) {
     }
 }
 
-/// Removes a friend by exact name.
+/// Removes a friend by exact name. Friends who take part in memories are kept, so the
+/// history stays intact.
 pub async fn remove_friend(name: String) {
-    let mut config = match read_config().await {
-        Ok(config) => config,
+    match count_friend_memories(&name).await {
+        Ok((0, 0)) => {}
+        Ok((0, trashed_count)) => {
+            println!(
+                "\"{name}\" takes part in {trashed_count} undone memory(ies), please purge the trash first"
+            );
+            return;
+        }
+        Ok((memory_count, _)) => {
+            println!(
+                "\"{name}\" takes part in {memory_count} memory(ies), please change their participants or remove them first"
+            );
+            return;
+        }
         Err(err) => {
             eprintln!("Failed to read data: {err}");
             return;
         }
-    };
-
-    let old_len = config.friends.len();
-    config.friends.retain(|friend| friend.name != name);
-    if old_len == config.friends.len() {
-        println!("Name \"{name}\" not found");
-        return;
     }
 
-    if let Err(err) = write_config(&config).await {
-        eprintln!("Failed to write data: {err}");
+    match delete_friend(&name).await {
+        Ok(true) => {}
+        Ok(false) => {
+            println!("Name \"{name}\" not found");
+            return;
+        }
+        Err(err) => {
+            eprintln!("Failed to write data: {err}");
+            return;
+        }
+    }
+    // Every other friend's share of past reductions changes without this one.
+    if let Err(err) = rebuild_chances_from_memories().await {
+        eprintln!("Failed to rebuild chances: {err}");
     }
 }
 
@@ -329,7 +660,7 @@ pub async fn edit_friend(name: String, new_name: Option<String>, new_level: Opti
     }
 
     if let Some(new_level_value) = new_level {
-        if let Some(default_level_chance) = level_default_chance(&new_level_value) {
+        if let Some(default_level_chance) = utils::level_chance(&config, &new_level_value) {
             config.friends[index].level = new_level_value;
             config.friends[index].chance = default_level_chance;
         } else {
@@ -343,27 +674,6 @@ pub async fn edit_friend(name: String, new_name: Option<String>, new_level: Opti
     }
 }
 
-/// Adds an `aji` friend.
-pub async fn add_aji(name: String) {
-    if let Some(friend_info) = make_friend(name, "aji") {
-        add_friend(friend_info).await;
-    }
-}
-
-/// Adds a `ki` friend.
-pub async fn add_ki(name: String) {
-    if let Some(friend_info) = make_friend(name, "ki") {
-        add_friend(friend_info).await;
-    }
-}
-
-/// Adds a `chi` friend.
-pub async fn add_chi(name: String) {
-    if let Some(friend_info) = make_friend(name, "chi") {
-        add_friend(friend_info).await;
-    }
-}
-
 /// Adds multiple friends with the same level.
 pub async fn add_many_friends(level: String, names: Vec<String>) {
     if names.is_empty() {
@@ -378,6 +688,10 @@ pub async fn add_many_friends(level: String, names: Vec<String>) {
             return;
         }
     };
+    if utils::level_chance(&config, &level).is_none() {
+        println!("Level \"{level}\" not found");
+        return;
+    }
 
     let mut duplicate_names = Vec::new();
     let mut added_count = 0;
@@ -388,7 +702,7 @@ pub async fn add_many_friends(level: String, names: Vec<String>) {
             continue;
         }
 
-        if let Some(friend_info) = make_friend(name, &level) {
+        if let Some(friend_info) = make_friend(&config, name, &level) {
             config.friends.push(friend_info);
             added_count += 1;
         }
@@ -410,7 +724,7 @@ pub async fn add_many_friends(level: String, names: Vec<String>) {
 }
 
 /// Lists friends, optionally filtered by level and/or sorted by chance.
-pub async fn list_friends(level_filter: Option<String>, sort_chance: bool) {
+pub async fn list_friends(level_filter: Option<String>, sort_chance: bool, format: OutputFormat) {
     let config = match read_config().await {
         Ok(config) => config,
         Err(err) => {
@@ -423,16 +737,17 @@ pub async fn list_friends(level_filter: Option<String>, sort_chance: bool) {
         filtered_config.friends.retain(|friend| friend.level == level_filter);
     }
 
-    let rendered_list = if sort_chance {
-        utils::list_friends_by_chance(&filtered_config)
-    } else {
-        utils::list_friends(&filtered_config)
+    let rendered_list = match (format, sort_chance) {
+        (OutputFormat::Text, true) => utils::list_friends_by_chance(&filtered_config),
+        (OutputFormat::Text, false) => utils::list_friends(&filtered_config),
+        (_, true) => output::render(format, utils::sort_friends_by_chance(&filtered_config)),
+        (_, false) => output::render(format, utils::sort_friends_by_name(&filtered_config)),
     };
     println!("{rendered_list}");
 }
 
 /// Searches friends by case-insensitive partial name.
-pub async fn search_friends(query: String) {
+pub async fn search_friends(query: String, format: OutputFormat) {
     let config = match read_config().await {
         Ok(config) => config,
         Err(err) => {
@@ -441,50 +756,253 @@ pub async fn search_friends(query: String) {
         }
     };
 
-    println!("{}", utils::search_friends(&config, &query));
+    let rendered_list = match format {
+        OutputFormat::Text => utils::search_friends(&config, &query),
+        _ => output::render(format, utils::find_friends(&config, &query)),
+    };
+    println!("{rendered_list}");
 }
 
-/// Suggests one friend using weighted random chance.
-pub async fn suggest() {
-    let config = match read_config().await {
-        Ok(config) => config,
+/// Everything needed to pick a suggestion, with chances as of now.
+struct SuggestionContext {
+    config: AkcConfig,
+    constraints: constraint::Constraints,
+    days_since_last_memory: HashMap<String, f64>,
+    /// Friends who are snoozed until later.
+    snoozed_names: HashSet<String>,
+    /// Level of the friend suggested last.
+    last_suggested_level: Option<String>,
+}
+
+impl SuggestionContext {
+    /// Friends that may be suggested right now, those overdue for their interval first.
+    fn candidates(&self) -> [Vec<&FriendInfo>; 2] {
+        let awake = utils::filter_config_by_enough_chance(&self.config)
+            .into_iter()
+            .filter(|friend_info| !self.snoozed_names.contains(&friend_info.name))
+            .collect();
+        utils::apply_constraints(awake, &self.constraints, &self.days_since_last_memory)
+    }
+
+    /// Builds the given strategy, or the default one, which continues from the stored
+    /// suggestions.
+    fn strategy(
+        &self,
+        kind: Option<StrategyKind>,
+        temperature: Option<f64>,
+    ) -> Box<dyn SuggestionStrategy> {
+        strategy::build_strategy(
+            kind.unwrap_or(self.config.settings.strategy),
+            &self.config,
+            &self.days_since_last_memory,
+            temperature.unwrap_or(self.config.settings.softmax_temperature),
+            self.last_suggested_level.clone(),
+        )
+    }
+}
+
+async fn read_suggestion_context() -> Result<SuggestionContext, sqlx::Error> {
+    let mut config = read_config().await?;
+    let constraints = constraint::read_constraints().await?;
+    let days_since_last_memory = read_days_since_last_memory().await?;
+    if config.settings.chance_model == ChanceModel::Recovery {
+        utils::recover_chances(&mut config, &days_since_last_memory);
+    }
+    let skip_counts = feedback::read_skip_counts().await?;
+    utils::apply_skip_penalties(&mut config, &skip_counts);
+    let snoozed_names = feedback::read_snoozed_names().await?;
+    let last_suggested_level = feedback::read_last_suggested_level().await?;
+    Ok(SuggestionContext {
+        config,
+        constraints,
+        days_since_last_memory,
+        snoozed_names,
+        last_suggested_level,
+    })
+}
+
+/// Returns a generator seeded with `seed`, so suggestions can be reproduced, or a randomly
+/// seeded one. ChaCha8 is used since its output, unlike `StdRng`'s, is fixed across `rand`
+/// versions.
+pub fn suggestion_rng(seed: Option<u64>) -> ChaCha8Rng {
+    match seed {
+        Some(seed) => ChaCha8Rng::seed_from_u64(seed),
+        None => ChaCha8Rng::from_rng(&mut rand::rng()),
+    }
+}
+
+/// Draws up to `count` distinct suggestions from the candidates of `context` with
+/// `strategy` and `rng`.
+fn draw_suggestions<R: Rng + ?Sized>(
+    context: &SuggestionContext,
+    count: usize,
+    companions: Option<&HashMap<String, HashSet<String>>>,
+    strategy: &mut dyn SuggestionStrategy,
+    rng: &mut R,
+) -> Vec<Suggestion> {
+    let candidates = context.candidates();
+    // Taken before drawing, since later friends are drawn among fewer candidates.
+    let probabilities = candidates
+        .iter()
+        .flat_map(|tier| strategy::probabilities(strategy, tier))
+        .collect::<HashMap<String, f64>>();
+    utils::sample_without_replacement(&candidates, count, companions, strategy, rng)
+        .into_iter()
+        .map(|friend| Suggestion {
+            name: friend.name.clone(),
+            level: friend.level.clone(),
+            chance: friend.chance,
+            probability: probabilities.get(&friend.name).copied().unwrap_or(0.0) * 100.0,
+        })
+        .collect()
+}
+
+/// Reads the suggestion context and keeps only the friends matching the filter of
+/// `options`, printing why if the options are invalid.
+async fn read_filtered_suggestion_context(options: &SuggestOptions) -> Option<SuggestionContext> {
+    let mut context = match read_suggestion_context().await {
+        Ok(context) => context,
         Err(err) => {
             eprintln!("Failed to read data: {err}");
-            return;
+            return None;
         }
     };
-    let filtered_config = utils::filter_config_by_enough_chance(&config);
-    if filtered_config.is_empty() {
-        println!("No friend to suggest");
-        return;
+    let strategy = options.strategy.unwrap_or(context.config.settings.strategy);
+    if options.temperature.is_some() && strategy != StrategyKind::Softmax {
+        println!(
+            "Temperature only applies to the softmax strategy, not {strategy}, please pass --strategy softmax"
+        );
+        return None;
+    }
+    let filter = &options.filter;
+    if let Some(ref level) = filter.level {
+        if utils::level_chance(&context.config, level).is_none() {
+            println!("Level \"{level}\" not found");
+            return None;
+        }
+    }
+    if let Some(ref kind) = filter.for_kind {
+        if utils::kind_reduction(&context.config, kind).is_none() {
+            println!("Memory kind \"{kind}\" not found");
+            return None;
+        }
     }
+    let unknown_names = utils::get_unknown_names(&context.config, &filter.exclude);
+    if !unknown_names.is_empty() {
+        let unknown_names_string = unknown_names
+            .iter()
+            .map(|&unknown_name| unknown_name.to_owned())
+            .collect::<Vec<String>>()
+            .join(", ");
+        println!("The following names are not added yet: {unknown_names_string}");
+        return None;
+    }
+    let tagged_names = match filter.tag {
+        Some(ref tag) => match read_tagged_friend_names(tag).await {
+            Ok(tagged_names) => Some(tagged_names),
+            Err(err) => {
+                eprintln!("Failed to read data: {err}");
+                return None;
+            }
+        },
+        None => None,
+    };
+    utils::filter_suggestion_candidates(&mut context.config, filter, tagged_names.as_ref());
+    Some(context)
+}
 
-    let mut rng = rand::rng();
-    let weighted_dist = match WeightedIndex::new(filtered_config.iter().map(|friend| friend.chance))
-    {
-        Ok(weighted_dist) => weighted_dist,
-        Err(err) => {
-            eprintln!("Failed to suggest a friend: {err}");
-            return;
+/// Suggests one or more distinct friends picked by the chosen or default strategy, drawing
+/// with `rng`.
+pub async fn suggest<R: Rng + ?Sized>(options: SuggestOptions, format: OutputFormat, rng: &mut R) {
+    let Some(context) = read_filtered_suggestion_context(&options).await else {
+        return;
+    };
+    let companions = if options.group {
+        match read_companions().await {
+            Ok(companions) => Some(companions),
+            Err(err) => {
+                eprintln!("Failed to read memories: {err}");
+                return;
+            }
         }
+    } else {
+        None
     };
-    let suggested_friend = filtered_config[weighted_dist.sample(&mut rng)];
-    println!("Suggested friend: {}", suggested_friend.name);
+
+    let mut strategy = context.strategy(options.strategy, options.temperature);
+    let mut suggestions = draw_suggestions(
+        &context,
+        options.count,
+        companions.as_ref(),
+        &mut *strategy,
+        rng,
+    );
+    // A single friend drawn is not a group.
+    if options.group && suggestions.len() < 2 {
+        suggestions.clear();
+    }
+    if let Err(err) = feedback::record_suggestions(&suggestions).await {
+        eprintln!("Failed to write data: {err}");
+        return;
+    }
+
+    if format != OutputFormat::Text {
+        println!("{}", output::render(format, &suggestions));
+        return;
+    }
+    let names = suggestions
+        .iter()
+        .map(|suggestion| suggestion.name.clone())
+        .collect::<Vec<String>>()
+        .join(", ");
+    match suggestions.len() {
+        0 if options.group => println!("No group to suggest"),
+        0 => println!("No friend to suggest"),
+        _ if options.group => println!("Suggested group: {names}"),
+        1 => println!("Suggested friend: {names}"),
+        _ => println!("Suggested friends: {names}"),
+    }
 }
 
-async fn add_memory(kind: &str, reduction: f64, names: &[String]) {
+/// Records a memory of the given kind with one or more friends, optionally backdated.
+/// Returns whether the memory was saved.
+pub async fn add_memory(
+    kind: &str,
+    names: &[String],
+    date: Option<DateInput>,
+    details: MemoryDetails,
+) -> bool {
+    let names = &utils::unique_names(names);
     if names.is_empty() {
         println!("Please specify at least one name");
-        return;
+        return false;
     }
+    let created_at = match date {
+        Some(ref date) => match memory::resolve_date(date).await {
+            Ok((_, true)) => {
+                println!("Memory date can't be in the future");
+                return false;
+            }
+            Ok((created_at, false)) => Some(created_at),
+            Err(err) => {
+                eprintln!("Failed to read data: {err}");
+                return false;
+            }
+        },
+        None => None,
+    };
 
     let mut config = match read_config().await {
         Ok(config) => config,
         Err(err) => {
             eprintln!("Failed to read data: {err}");
-            return;
+            return false;
         }
     };
+    let Some(reduction) = utils::kind_reduction(&config, kind) else {
+        println!("Memory kind \"{kind}\" not found");
+        return false;
+    };
     let unknown_names = utils::get_unknown_names(&config, names);
 
     if !unknown_names.is_empty() {
@@ -497,6 +1015,7 @@ async fn add_memory(kind: &str, reduction: f64, names: &[String]) {
             "The following names are not added yet: {}",
             unknown_names_string
         );
+        false
     } else {
         let total_reduction = reduction * names.len() as f64;
         let current_total_chance = utils::get_config_total_chance(&config, names);
@@ -507,36 +1026,25 @@ async fn add_memory(kind: &str, reduction: f64, names: &[String]) {
 
         if let Err(err) = write_config(&config).await {
             eprintln!("Failed to write data: {err}");
-            return;
+            return false;
         }
 
-        if let Err(err) = save_memory(kind, names).await {
+        if let Err(err) = save_memory(kind, names, created_at.as_deref(), &details).await {
             eprintln!("Failed to save memory: {err}");
+            return false;
         }
-    }
-}
-
-/// Records a `hangout` memory.
-pub async fn add_hangout(names: &[String]) {
-    add_memory("hangout", default_reduction::HANGOUT, names).await
-}
 
-/// Records a `video-call` memory.
-pub async fn add_video_call(names: &[String]) {
-    add_memory("video-call", default_reduction::VIDEO_CALL, names).await
-}
-
-/// Records a `call` memory.
-pub async fn add_call(names: &[String]) {
-    add_memory("call", default_reduction::CALL, names).await
-}
-
-/// Records a `text` memory.
-pub async fn add_text(names: &[String]) {
-    add_memory("text", default_reduction::TEXT, names).await
+        // A backdated memory may precede others, so history has to be replayed in order.
+        if created_at.is_some() {
+            if let Err(err) = rebuild_chances_from_memories().await {
+                eprintln!("Failed to rebuild chances: {err}");
+            }
+        }
+        true
+    }
 }
 
-/// Undoes the latest recorded memory.
+/// Moves the latest recorded memory into the trash, from where it can be redone.
 pub async fn undo_memory() {
     let memories = match read_memories().await {
         Ok(memories) => memories,
@@ -545,12 +1053,12 @@ pub async fn undo_memory() {
             return;
         }
     };
-    let Some(last_memory) = memories.last() else {
+    let Some(last_memory) = memories.iter().max_by_key(|memory| memory.id) else {
         println!("No memory to undo");
         return;
     };
 
-    match delete_memory(last_memory.id).await {
+    match trash::trash_memory(last_memory.id).await {
         Ok(true) => {}
         Ok(false) => {
             println!("No memory to undo");
@@ -588,7 +1096,49 @@ pub async fn remove_memory(id: i64) {
 
 #[cfg(test)]
 mod test {
-    use super::{deserialize_memory_names, get_unit_added_chance, serialize_memory_names};
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+    use std::collections::{HashMap, HashSet};
+
+    use super::{
+        draw_suggestions, friend_info, get_unit_added_chance, group_memory_rows, suggestion_rng,
+        AkcConfig, MemoryInfo, SuggestionContext,
+    };
+
+    fn suggestion_context() -> SuggestionContext {
+        SuggestionContext {
+            config: AkcConfig {
+                friends: vec![
+                    friend_info("John", "aji", 30.0),
+                    friend_info("Doe", "aji", 10.0),
+                    friend_info("Jane", "aji", 20.0),
+                    friend_info("Low", "aji", 0.1),
+                ],
+                ..Default::default()
+            },
+            constraints: Default::default(),
+            days_since_last_memory: HashMap::new(),
+            snoozed_names: HashSet::from(["Jane".to_owned()]),
+            last_suggested_level: None,
+        }
+    }
+
+    fn suggested_names(
+        context: &SuggestionContext,
+        count: usize,
+        rng: &mut ChaCha8Rng,
+    ) -> Vec<String> {
+        draw_suggestions(
+            context,
+            count,
+            None,
+            &mut *context.strategy(None, None),
+            rng,
+        )
+        .into_iter()
+        .map(|suggestion| suggestion.name)
+        .collect()
+    }
 
     #[test]
     fn test_get_unit_added_chance_when_total_is_zero() {
@@ -601,16 +1151,75 @@ mod test {
     }
 
     #[test]
-    fn test_serialize_memory_names() {
-        let names = vec!["A".to_owned(), "B".to_owned()];
-        assert_eq!(serialize_memory_names(&names), "A\nB");
+    fn test_draw_suggestions_with_seed() {
+        let context = suggestion_context();
+
+        for seed in 0..20 {
+            let names = suggested_names(&context, 2, &mut suggestion_rng(Some(seed)));
+            assert_eq!(
+                names,
+                suggested_names(&context, 2, &mut suggestion_rng(Some(seed)))
+            );
+            assert_eq!(names.len(), 2);
+            assert_ne!(names[0], names[1]);
+            // Snoozed friends and friends below the minimum chance are never suggested.
+            assert!(!names.contains(&"Jane".to_owned()));
+            assert!(!names.contains(&"Low".to_owned()));
+        }
+        // The same seed has to give the same suggestion on every machine and `rand` version.
+        let first_names = (0..8)
+            .map(|seed| suggested_names(&context, 1, &mut suggestion_rng(Some(seed))).remove(0))
+            .collect::<Vec<String>>();
+        assert_eq!(
+            first_names,
+            ["John", "John", "Doe", "John", "John", "John", "John", "John"]
+        );
     }
 
     #[test]
-    fn test_deserialize_memory_names() {
-        assert_eq!(
-            deserialize_memory_names("A\nB"),
-            vec!["A".to_owned(), "B".to_owned()]
+    fn test_draw_suggestions_probabilities() {
+        let context = suggestion_context();
+        let mut rng = ChaCha8Rng::seed_from_u64(7);
+
+        let mut suggestions = draw_suggestions(
+            &context,
+            5,
+            None,
+            &mut *context.strategy(None, None),
+            &mut rng,
         );
+        suggestions.sort_by(|left, right| left.name.cmp(&right.name));
+        // The friend drawn last keeps the probability they had before the first draw.
+        let probabilities = suggestions
+            .iter()
+            .map(|suggestion| (suggestion.name.as_str(), suggestion.probability))
+            .collect::<Vec<(&str, f64)>>();
+        assert_eq!(probabilities, [("Doe", 25.0), ("John", 75.0)]);
+    }
+
+    #[test]
+    fn test_group_memory_rows() {
+        let memory = |id: i64, kind: &str| MemoryInfo {
+            id,
+            kind: kind.to_owned(),
+            names: Vec::new(),
+            created_at: "2026-10-11 18:00:00".to_owned(),
+            note: None,
+            place: None,
+            tags: Vec::new(),
+        };
+        let rows = vec![
+            (memory(1, "hangout"), Some("A".to_owned())),
+            (memory(1, "hangout"), Some("B".to_owned())),
+            (memory(2, "text"), None),
+            (memory(3, "call"), Some("A".to_owned())),
+        ];
+        let memories = group_memory_rows(rows);
+
+        assert_eq!(memories.len(), 3);
+        assert_eq!(memories[0].names, vec!["A".to_owned(), "B".to_owned()]);
+        assert!(memories[1].names.is_empty());
+        assert_eq!(memories[2].kind, "call");
+        assert_eq!(memories[2].names, vec!["A".to_owned()]);
     }
 }
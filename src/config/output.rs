@@ -0,0 +1,356 @@
+use clap::ValueEnum;
+use serde::Serialize;
+
+use super::{
+    constraint::ConstraintRecord, feedback::SuggestionRecord, plan::PlanInfo,
+    report::FairnessRecord, status::FriendStatus, trash::TrashedMemory, utils, FriendInfo,
+    KindInfo, LevelInfo, MemoryInfo, Suggestion,
+};
+
+/// How read commands print their results.
+#[derive(ValueEnum, Clone, Copy, Debug, Default, PartialEq)]
+pub enum OutputFormat {
+    /// Human-friendly text
+    #[default]
+    Text,
+    Json,
+    Csv,
+    /// Aligned columns
+    Table,
+}
+
+/// A row of structured output.
+pub trait Record: Serialize {
+    const HEADERS: &'static [&'static str];
+
+    /// Cells in the order of `HEADERS`.
+    fn fields(&self) -> Vec<String>;
+}
+
+fn escape_csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_owned()
+    }
+}
+
+fn render_csv(headers: &[&str], rows: &[Vec<String>]) -> String {
+    let mut lines = vec![headers.join(",")];
+    lines.extend(rows.iter().map(|row| {
+        row.iter()
+            .map(|field| escape_csv_field(field))
+            .collect::<Vec<String>>()
+            .join(",")
+    }));
+    lines.join("\n")
+}
+
+/// Parses CSV text into rows of fields, skipping empty lines.
+pub fn parse_csv(text: &str) -> Vec<Vec<String>> {
+    let mut rows = Vec::new();
+    let mut row = Vec::new();
+    let mut field = String::new();
+    let mut in_quotes = false;
+    let mut chars = text.chars().peekable();
+    while let Some(char) = chars.next() {
+        match char {
+            '"' if in_quotes && chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            }
+            '"' => in_quotes = !in_quotes,
+            ',' if !in_quotes => row.push(std::mem::take(&mut field)),
+            '\r' if !in_quotes => {}
+            '\n' if !in_quotes => {
+                row.push(std::mem::take(&mut field));
+                if row.iter().any(|field| !field.is_empty()) {
+                    rows.push(std::mem::take(&mut row));
+                } else {
+                    row.clear();
+                }
+            }
+            _ => field.push(char),
+        }
+    }
+    row.push(field);
+    if row.iter().any(|field| !field.is_empty()) {
+        rows.push(row);
+    }
+    rows
+}
+
+/// Renders records as JSON, CSV or a table. Text output is command specific, so it is
+/// rendered as a table here too.
+pub fn render<'a, T: Record + 'a>(
+    format: OutputFormat,
+    records: impl IntoIterator<Item = &'a T>,
+) -> String {
+    let records = records.into_iter().collect::<Vec<&T>>();
+    match format {
+        OutputFormat::Json => serde_json::to_string_pretty(&records)
+            .unwrap_or_else(|err| format!("Failed to serialize output: {err}")),
+        OutputFormat::Csv => render_csv(
+            T::HEADERS,
+            &records
+                .iter()
+                .map(|record| record.fields())
+                .collect::<Vec<Vec<String>>>(),
+        ),
+        OutputFormat::Text | OutputFormat::Table => {
+            let headers = T::HEADERS
+                .iter()
+                .map(|header| header.to_uppercase())
+                .collect::<Vec<String>>();
+            utils::render_table(
+                &headers.iter().map(String::as_str).collect::<Vec<&str>>(),
+                &records
+                    .iter()
+                    .map(|record| record.fields())
+                    .collect::<Vec<Vec<String>>>(),
+            )
+        }
+    }
+}
+
+impl Record for FriendInfo {
+    const HEADERS: &'static [&'static str] = &["name", "level", "chance"];
+
+    fn fields(&self) -> Vec<String> {
+        vec![
+            self.name.clone(),
+            self.level.clone(),
+            format!("{:.2}", self.chance),
+        ]
+    }
+}
+
+impl Record for LevelInfo {
+    const HEADERS: &'static [&'static str] = &["name", "chance"];
+
+    fn fields(&self) -> Vec<String> {
+        vec![self.name.clone(), format!("{:.2}", self.chance)]
+    }
+}
+
+impl Record for KindInfo {
+    const HEADERS: &'static [&'static str] = &["name", "reduction"];
+
+    fn fields(&self) -> Vec<String> {
+        vec![self.name.clone(), format!("{:.2}", self.reduction)]
+    }
+}
+
+impl Record for MemoryInfo {
+    const HEADERS: &'static [&'static str] = &[
+        "id",
+        "kind",
+        "participants",
+        "created_at",
+        "place",
+        "tags",
+        "note",
+    ];
+
+    fn fields(&self) -> Vec<String> {
+        vec![
+            self.id.to_string(),
+            self.kind.clone(),
+            self.names.join(", "),
+            self.created_at.clone(),
+            self.place.clone().unwrap_or_default(),
+            self.tags.join(", "),
+            self.note.clone().unwrap_or_default(),
+        ]
+    }
+}
+
+impl Record for TrashedMemory {
+    const HEADERS: &'static [&'static str] = &[
+        "id",
+        "kind",
+        "participants",
+        "created_at",
+        "place",
+        "tags",
+        "note",
+        "trashed_at",
+    ];
+
+    fn fields(&self) -> Vec<String> {
+        let mut fields = self.memory.fields();
+        fields.push(self.trashed_at.clone());
+        fields
+    }
+}
+
+impl Record for PlanInfo {
+    const HEADERS: &'static [&'static str] = &["id", "kind", "participants", "planned_on", "note"];
+
+    fn fields(&self) -> Vec<String> {
+        vec![
+            self.id.to_string(),
+            self.kind.clone(),
+            self.participants.join(", "),
+            self.planned_on.clone(),
+            self.note.clone().unwrap_or_default(),
+        ]
+    }
+}
+
+impl Record for Suggestion {
+    const HEADERS: &'static [&'static str] = &["name", "level", "chance", "probability"];
+
+    fn fields(&self) -> Vec<String> {
+        vec![
+            self.name.clone(),
+            self.level.clone(),
+            format!("{:.2}", self.chance),
+            format!("{:.4}", self.probability),
+        ]
+    }
+}
+
+impl Record for SuggestionRecord {
+    const HEADERS: &'static [&'static str] = &[
+        "id",
+        "name",
+        "level",
+        "probability",
+        "outcome",
+        "suggested_at",
+    ];
+
+    fn fields(&self) -> Vec<String> {
+        vec![
+            self.id.to_string(),
+            self.name.clone(),
+            self.level.clone(),
+            self.probability
+                .map_or(String::new(), |probability| format!("{probability:.4}")),
+            self.outcome.clone().unwrap_or_default(),
+            self.suggested_at.clone(),
+        ]
+    }
+}
+
+impl Record for ConstraintRecord {
+    const HEADERS: &'static [&'static str] = &["target", "name", "cooldown_days", "interval_days"];
+
+    fn fields(&self) -> Vec<String> {
+        vec![
+            self.target.to_owned(),
+            self.name.clone(),
+            self.cooldown_days
+                .map_or(String::new(), |days| days.to_string()),
+            self.interval_days
+                .map_or(String::new(), |days| days.to_string()),
+        ]
+    }
+}
+
+impl Record for FriendStatus {
+    const HEADERS: &'static [&'static str] = &[
+        "name",
+        "level",
+        "chance",
+        "probability",
+        "last_memory_date",
+        "last_memory_kind",
+        "memory_counts",
+    ];
+
+    fn fields(&self) -> Vec<String> {
+        vec![
+            self.name.clone(),
+            self.level.clone(),
+            format!("{:.2}", self.chance),
+            format!("{:.4}", self.probability),
+            self.last_memory_date.clone().unwrap_or_default(),
+            self.last_memory_kind.clone().unwrap_or_default(),
+            self.memory_counts
+                .iter()
+                .map(|(kind, count)| format!("{kind}: {count}"))
+                .collect::<Vec<String>>()
+                .join(", "),
+        ]
+    }
+}
+
+impl Record for FairnessRecord {
+    const HEADERS: &'static [&'static str] = &[
+        "scope",
+        "name",
+        "suggestions",
+        "suggestion_share",
+        "average_probability",
+        "memories",
+        "memory_share",
+    ];
+
+    fn fields(&self) -> Vec<String> {
+        vec![
+            self.scope.to_owned(),
+            self.name.clone(),
+            self.suggestions.to_string(),
+            format!("{:.2}", self.suggestion_share),
+            self.average_probability
+                .map_or(String::new(), |probability| format!("{probability:.4}")),
+            self.memories.to_string(),
+            format!("{:.2}", self.memory_share),
+        ]
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{escape_csv_field, parse_csv, render, OutputFormat};
+    use crate::config::LevelInfo;
+
+    #[test]
+    fn test_escape_csv_field() {
+        assert_eq!(escape_csv_field("Negar"), "Negar");
+        assert_eq!(escape_csv_field("Doe, John"), "\"Doe, John\"");
+        assert_eq!(escape_csv_field("say \"hi\""), "\"say \"\"hi\"\"\"");
+    }
+
+    #[test]
+    fn test_parse_csv() {
+        assert_eq!(
+            parse_csv("name,level\r\n\"Doe, John\",aji\n\n\"say \"\"hi\"\"\",\"multi\nline\""),
+            vec![
+                vec!["name".to_owned(), "level".to_owned()],
+                vec!["Doe, John".to_owned(), "aji".to_owned()],
+                vec!["say \"hi\"".to_owned(), "multi\nline".to_owned()],
+            ]
+        );
+        assert!(parse_csv("\n\n").is_empty());
+    }
+
+    #[test]
+    fn test_render() {
+        let levels = vec![
+            LevelInfo {
+                name: "aji".to_owned(),
+                chance: 50.0,
+            },
+            LevelInfo {
+                name: "close, family".to_owned(),
+                chance: 7.5,
+            },
+        ];
+
+        assert_eq!(
+            render(OutputFormat::Csv, &levels),
+            "name,chance\naji,50.00\n\"close, family\",7.50"
+        );
+        assert_eq!(
+            render(OutputFormat::Table, &levels),
+            "NAME           CHANCE\naji            50.00\nclose, family  7.50"
+        );
+        assert_eq!(
+            render(OutputFormat::Json, &levels[..1]),
+            "[\n  {\n    \"name\": \"aji\",\n    \"chance\": 50.0\n  }\n]"
+        );
+    }
+}
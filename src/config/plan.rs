@@ -0,0 +1,330 @@
+use serde::Serialize;
+use sqlx::Row;
+use std::{fs, path::Path};
+
+use super::{
+    add_memory,
+    content_line::{escape_value, fold_line},
+    memory::{parse_date_input, DateInput},
+    open_pool,
+    output::{self, OutputFormat},
+    read_config, utils, MemoryDetails,
+};
+
+/// A future intention to meet one or more friends.
+#[derive(Serialize)]
+pub struct PlanInfo {
+    pub id: i64,
+    pub kind: String,
+    pub participants: Vec<String>,
+    /// `YYYY-MM-DD`, or `YYYY-MM-DD HH:MM:SS` in UTC if a time was given.
+    pub planned_on: String,
+    pub note: Option<String>,
+    pub created_at: String,
+}
+
+/// Parses `YYYY-MM-DD` or `YYYY-MM-DD HH:MM[:SS]`, keeping date-only plans without a time.
+pub fn parse_plan_date(value: &str) -> Option<String> {
+    let value = value.trim();
+    if utils::is_valid_date(value) {
+        return Some(value.to_owned());
+    }
+    match parse_date_input(value)? {
+        DateInput::Absolute(datetime) => Some(datetime),
+        DateInput::DaysAgo(_) => None,
+    }
+}
+
+/// Converts a plan date to an iCalendar `DTSTART` property.
+fn event_start(planned_on: &str) -> String {
+    let digits = planned_on
+        .chars()
+        .filter(char::is_ascii_digit)
+        .collect::<String>();
+    match digits.split_at_checked(8) {
+        Some((date, time)) if !time.is_empty() => format!("DTSTART:{date}T{time}Z"),
+        _ => format!("DTSTART;VALUE=DATE:{digits}"),
+    }
+}
+
+/// Renders plans as an iCalendar document with one event per plan.
+fn render_plans_ics(plans: &[PlanInfo]) -> String {
+    let mut lines = vec![
+        "BEGIN:VCALENDAR".to_owned(),
+        "VERSION:2.0".to_owned(),
+        "PRODID:-//akc//plans//EN".to_owned(),
+    ];
+    for plan in plans {
+        let summary = format!("{} with {}", plan.kind, plan.participants.join(", "));
+        let created_at = plan
+            .created_at
+            .chars()
+            .filter(char::is_ascii_digit)
+            .collect::<String>();
+        lines.extend([
+            "BEGIN:VEVENT".to_owned(),
+            format!("UID:plan-{}@akc", plan.id),
+            format!("DTSTAMP:{}T{}Z", &created_at[..8], &created_at[8..]),
+            event_start(&plan.planned_on),
+            format!("SUMMARY:{}", escape_value(&summary)),
+            format!("CATEGORIES:{}", escape_value(&plan.kind)),
+        ]);
+        if let Some(ref note) = plan.note {
+            lines.push(format!("DESCRIPTION:{}", escape_value(note)));
+        }
+        lines.push("END:VEVENT".to_owned());
+    }
+    lines.push("END:VCALENDAR".to_owned());
+    lines.iter().map(|line| fold_line(line)).collect()
+}
+
+async fn insert_plan(
+    kind: &str,
+    names: &[String],
+    planned_on: &str,
+    note: Option<&str>,
+) -> Result<i64, sqlx::Error> {
+    let pool = open_pool().await?;
+    let mut transaction = pool.begin().await?;
+    let plan_id = sqlx::query("INSERT INTO plans (kind, planned_on, note) VALUES (?1, ?2, ?3)")
+        .bind(kind)
+        .bind(planned_on)
+        .bind(note)
+        .execute(&mut *transaction)
+        .await?
+        .last_insert_rowid();
+    for name in names {
+        sqlx::query(
+            "INSERT OR IGNORE INTO plan_participants (plan_id, friend_id)
+                SELECT ?1, id FROM friends WHERE name = ?2",
+        )
+        .bind(plan_id)
+        .bind(name)
+        .execute(&mut *transaction)
+        .await?;
+    }
+    transaction.commit().await?;
+    Ok(plan_id)
+}
+
+async fn is_before_today(planned_on: &str) -> Result<bool, sqlx::Error> {
+    let pool = open_pool().await?;
+    let row = sqlx::query("SELECT substr(?1, 1, 10) < date('now') AS is_past")
+        .bind(planned_on)
+        .fetch_one(&pool)
+        .await?;
+    Ok(row.get("is_past"))
+}
+
+pub(super) async fn read_plans() -> Result<Vec<PlanInfo>, sqlx::Error> {
+    let pool = open_pool().await?;
+    let rows = sqlx::query(
+        "SELECT plans.id, plans.kind, plans.planned_on, plans.note, plans.created_at,
+            (SELECT group_concat(friends.name, char(10)) FROM plan_participants
+                JOIN friends ON friends.id = plan_participants.friend_id
+                WHERE plan_participants.plan_id = plans.id) AS names
+            FROM plans
+            ORDER BY plans.planned_on ASC, plans.id ASC",
+    )
+    .fetch_all(&pool)
+    .await?;
+    let plans = rows
+        .into_iter()
+        .map(|row| {
+            let names: Option<String> = row.get("names");
+            let mut participants = names
+                .map(|names| names.lines().map(str::to_owned).collect::<Vec<String>>())
+                .unwrap_or_default();
+            participants.sort();
+            PlanInfo {
+                id: row.get("id"),
+                kind: row.get("kind"),
+                participants,
+                planned_on: row.get("planned_on"),
+                note: row.get("note"),
+                created_at: row.get("created_at"),
+            }
+        })
+        .collect();
+    Ok(plans)
+}
+
+async fn delete_plan(id: i64) -> Result<bool, sqlx::Error> {
+    let pool = open_pool().await?;
+    let result = sqlx::query("DELETE FROM plans WHERE id = ?1")
+        .bind(id)
+        .execute(&pool)
+        .await?;
+    Ok(result.rows_affected() > 0)
+}
+
+/// Plans a memory of the given kind with one or more friends on a future date.
+pub async fn add_plan(kind: &str, names: &[String], planned_on: &str, note: Option<&str>) {
+    let names = &utils::unique_names(names);
+    if names.is_empty() {
+        println!("Please specify at least one name");
+        return;
+    }
+    match is_before_today(planned_on).await {
+        Ok(false) => {}
+        Ok(true) => {
+            println!("Plan date can't be in the past");
+            return;
+        }
+        Err(err) => {
+            eprintln!("Failed to read data: {err}");
+            return;
+        }
+    }
+
+    let config = match read_config().await {
+        Ok(config) => config,
+        Err(err) => {
+            eprintln!("Failed to read data: {err}");
+            return;
+        }
+    };
+    if utils::kind_reduction(&config, kind).is_none() {
+        println!("Memory kind \"{kind}\" not found");
+        return;
+    }
+    let unknown_names = utils::get_unknown_names(&config, names);
+    if !unknown_names.is_empty() {
+        let unknown_names_string = unknown_names
+            .iter()
+            .map(|&unknown_name| unknown_name.to_owned())
+            .collect::<Vec<String>>()
+            .join(", ");
+        println!("The following names are not added yet: {unknown_names_string}");
+        return;
+    }
+
+    match insert_plan(kind, names, planned_on, note).await {
+        Ok(id) => println!("Added plan {id}"),
+        Err(err) => eprintln!("Failed to write data: {err}"),
+    }
+}
+
+/// Lists plans, soonest first.
+pub async fn list_plans(format: OutputFormat) {
+    let plans = match read_plans().await {
+        Ok(plans) => plans,
+        Err(err) => {
+            eprintln!("Failed to read plans: {err}");
+            return;
+        }
+    };
+
+    println!("{}", output::render(format, &plans));
+}
+
+/// Records a plan as a memory and removes it. Plans done on or after their day are dated
+/// now, overdue ones keep their planned date.
+pub async fn complete_plan(id: i64) {
+    let plans = match read_plans().await {
+        Ok(plans) => plans,
+        Err(err) => {
+            eprintln!("Failed to read plans: {err}");
+            return;
+        }
+    };
+    let Some(plan) = plans.into_iter().find(|plan| plan.id == id) else {
+        println!("Plan id {id} not found");
+        return;
+    };
+    let date = match is_before_today(&plan.planned_on).await {
+        Ok(true) => parse_date_input(&plan.planned_on),
+        Ok(false) => None,
+        Err(err) => {
+            eprintln!("Failed to read data: {err}");
+            return;
+        }
+    };
+
+    let details = MemoryDetails {
+        note: plan.note,
+        ..Default::default()
+    };
+    if !add_memory(&plan.kind, &plan.participants, date, details).await {
+        return;
+    }
+    if let Err(err) = delete_plan(id).await {
+        eprintln!("Failed to delete plan: {err}");
+    }
+}
+
+/// Removes a plan without recording a memory.
+pub async fn remove_plan(id: i64) {
+    match delete_plan(id).await {
+        Ok(true) => {}
+        Ok(false) => println!("Plan id {id} not found"),
+        Err(err) => eprintln!("Failed to delete plan: {err}"),
+    }
+}
+
+/// Writes all plans as iCalendar events to `out`, or to stdout if no file is given.
+pub async fn export_plans_ics(out: Option<&Path>) {
+    let plans = match read_plans().await {
+        Ok(plans) => plans,
+        Err(err) => {
+            eprintln!("Failed to read plans: {err}");
+            return;
+        }
+    };
+
+    let text = render_plans_ics(&plans);
+    match out {
+        Some(path) => {
+            if let Err(err) = fs::write(path, text) {
+                eprintln!("Failed to write {}: {err}", path.display());
+            }
+        }
+        None => print!("{text}"),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{event_start, parse_plan_date, render_plans_ics, PlanInfo};
+
+    #[test]
+    fn test_parse_plan_date() {
+        assert_eq!(parse_plan_date("2026-10-25"), Some("2026-10-25".to_owned()));
+        assert_eq!(
+            parse_plan_date("2026-10-25 18:30"),
+            Some("2026-10-25 18:30:00".to_owned())
+        );
+        assert_eq!(parse_plan_date("yesterday"), None);
+        assert_eq!(parse_plan_date("2026-02-30"), None);
+    }
+
+    #[test]
+    fn test_event_start() {
+        assert_eq!(event_start("2026-10-25"), "DTSTART;VALUE=DATE:20261025");
+        assert_eq!(
+            event_start("2026-10-25 18:30:00"),
+            "DTSTART:20261025T183000Z"
+        );
+    }
+
+    #[test]
+    fn test_render_plans_ics() {
+        let plans = vec![PlanInfo {
+            id: 3,
+            kind: "hangout".to_owned(),
+            participants: vec!["Negar".to_owned(), "Sara".to_owned()],
+            planned_on: "2026-10-25".to_owned(),
+            note: Some("Picnic, if sunny".to_owned()),
+            created_at: "2026-10-18 09:15:00".to_owned(),
+        }];
+
+        assert_eq!(
+            render_plans_ics(&plans),
+            "BEGIN:VCALENDAR\r\nVERSION:2.0\r\nPRODID:-//akc//plans//EN\r\n\
+                BEGIN:VEVENT\r\nUID:plan-3@akc\r\nDTSTAMP:20261018T091500Z\r\n\
+                DTSTART;VALUE=DATE:20261025\r\nSUMMARY:hangout with Negar\\, Sara\r\n\
+                CATEGORIES:hangout\r\nDESCRIPTION:Picnic\\, if sunny\r\nEND:VEVENT\r\n\
+                END:VCALENDAR\r\n"
+        );
+    }
+}
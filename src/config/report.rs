@@ -0,0 +1,240 @@
+use serde::Serialize;
+use sqlx::Row;
+use std::collections::HashMap;
+
+use super::{
+    open_pool,
+    output::{self, OutputFormat},
+    read_config, utils, AkcConfig,
+};
+
+/// Suggestions of one friend or level next to the memories actually shared.
+#[derive(Serialize)]
+pub struct FairnessRecord {
+    /// `friend` or `level`.
+    pub scope: &'static str,
+    pub name: String,
+    pub suggestions: i64,
+    /// Percentage of all suggestions.
+    pub suggestion_share: f64,
+    /// Mean percentage probability when suggested, if any was stored.
+    pub average_probability: Option<f64>,
+    /// Memories taken part in.
+    pub memories: i64,
+    /// Percentage of all memory participations.
+    pub memory_share: f64,
+}
+
+/// Stored suggestions of a single friend.
+#[derive(Clone, Copy, Default)]
+struct SuggestionStats {
+    count: i64,
+    probability_sum: f64,
+    /// Suggestions with a stored probability.
+    probability_count: i64,
+}
+
+impl SuggestionStats {
+    fn add(&mut self, other: &SuggestionStats) {
+        self.count += other.count;
+        self.probability_sum += other.probability_sum;
+        self.probability_count += other.probability_count;
+    }
+}
+
+fn share(count: i64, total: i64) -> f64 {
+    if total == 0 {
+        return 0.0;
+    }
+    count as f64 * 100.0 / total as f64
+}
+
+/// Builds one record per friend, by name, followed by one per level.
+fn fairness_records(
+    config: &AkcConfig,
+    suggestion_stats: &HashMap<String, SuggestionStats>,
+    memory_counts: &HashMap<String, i64>,
+) -> Vec<FairnessRecord> {
+    let friends = utils::sort_friends_by_name(config);
+    let friend_stats = |name: &str| suggestion_stats.get(name).copied().unwrap_or_default();
+    let friend_memories = |name: &str| memory_counts.get(name).copied().unwrap_or_default();
+    let total_suggestions = friends
+        .iter()
+        .map(|friend| friend_stats(&friend.name).count)
+        .sum();
+    let total_memories = friends
+        .iter()
+        .map(|friend| friend_memories(&friend.name))
+        .sum();
+    let record = |scope, name: &str, stats: SuggestionStats, memories| FairnessRecord {
+        scope,
+        name: name.to_owned(),
+        suggestions: stats.count,
+        suggestion_share: share(stats.count, total_suggestions),
+        average_probability: (stats.probability_count > 0)
+            .then(|| stats.probability_sum / stats.probability_count as f64),
+        memories,
+        memory_share: share(memories, total_memories),
+    };
+
+    let mut records = friends
+        .iter()
+        .map(|friend| {
+            record(
+                "friend",
+                &friend.name,
+                friend_stats(&friend.name),
+                friend_memories(&friend.name),
+            )
+        })
+        .collect::<Vec<FairnessRecord>>();
+    for level in utils::sort_levels(config) {
+        let mut stats = SuggestionStats::default();
+        let mut memories = 0;
+        for friend in friends.iter().filter(|friend| friend.level == level.name) {
+            stats.add(&friend_stats(&friend.name));
+            memories += friend_memories(&friend.name);
+        }
+        records.push(record("level", &level.name, stats, memories));
+    }
+    records
+}
+
+async fn read_suggestion_stats() -> Result<HashMap<String, SuggestionStats>, sqlx::Error> {
+    let pool = open_pool().await?;
+    let rows = sqlx::query(
+        "SELECT friends.name, COUNT(*) AS count,
+            COALESCE(SUM(suggestions.probability), 0.0) AS probability_sum,
+            COUNT(suggestions.probability) AS probability_count
+            FROM suggestions
+            JOIN friends ON friends.id = suggestions.friend_id
+            GROUP BY friends.id",
+    )
+    .fetch_all(&pool)
+    .await?;
+    Ok(rows
+        .into_iter()
+        .map(|row| {
+            let stats = SuggestionStats {
+                count: row.get("count"),
+                probability_sum: row.get("probability_sum"),
+                probability_count: row.get("probability_count"),
+            };
+            (row.get("name"), stats)
+        })
+        .collect())
+}
+
+async fn read_memory_counts() -> Result<HashMap<String, i64>, sqlx::Error> {
+    let pool = open_pool().await?;
+    let rows = sqlx::query(
+        "SELECT friends.name, COUNT(*) AS count
+            FROM friends
+            JOIN memory_participants ON memory_participants.friend_id = friends.id
+            GROUP BY friends.id",
+    )
+    .fetch_all(&pool)
+    .await?;
+    Ok(rows
+        .into_iter()
+        .map(|row| (row.get("name"), row.get("count")))
+        .collect())
+}
+
+/// Compares how often each friend and level was suggested with how many memories were
+/// shared with them.
+pub async fn print_fairness_report(format: OutputFormat) {
+    let config = match read_config().await {
+        Ok(config) => config,
+        Err(err) => {
+            eprintln!("Failed to read data: {err}");
+            return;
+        }
+    };
+    let suggestion_stats = match read_suggestion_stats().await {
+        Ok(suggestion_stats) => suggestion_stats,
+        Err(err) => {
+            eprintln!("Failed to read suggestions: {err}");
+            return;
+        }
+    };
+    let memory_counts = match read_memory_counts().await {
+        Ok(memory_counts) => memory_counts,
+        Err(err) => {
+            eprintln!("Failed to read memories: {err}");
+            return;
+        }
+    };
+
+    let records = fairness_records(&config, &suggestion_stats, &memory_counts);
+    println!("{}", output::render(format, &records));
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashMap;
+
+    use super::{fairness_records, SuggestionStats};
+    use crate::config::{friend_info, AkcConfig, LevelInfo};
+
+    #[test]
+    fn test_fairness_records() {
+        let level = |name: &str, chance: f64| LevelInfo {
+            name: name.to_owned(),
+            chance,
+        };
+        let config = AkcConfig {
+            friends: vec![
+                friend_info("John", "aji", 10.0),
+                friend_info("Doe", "ki", 10.0),
+                friend_info("Jane", "aji", 10.0),
+            ],
+            levels: vec![level("ki", 5.0), level("aji", 10.0)],
+            ..Default::default()
+        };
+        let suggestion_stats = HashMap::from([
+            (
+                "John".to_owned(),
+                SuggestionStats {
+                    count: 3,
+                    probability_sum: 100.0,
+                    probability_count: 2,
+                },
+            ),
+            (
+                "Doe".to_owned(),
+                SuggestionStats {
+                    count: 1,
+                    probability_sum: 0.0,
+                    probability_count: 0,
+                },
+            ),
+        ]);
+        let memory_counts = HashMap::from([("Jane".to_owned(), 1), ("Doe".to_owned(), 3)]);
+
+        let records = fairness_records(&config, &suggestion_stats, &memory_counts)
+            .into_iter()
+            .map(|record| {
+                (
+                    record.scope,
+                    record.name,
+                    record.suggestions,
+                    record.suggestion_share,
+                    record.average_probability,
+                    record.memories,
+                    record.memory_share,
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            records,
+            vec![
+                ("friend", "Doe".to_owned(), 1, 25.0, None, 3, 75.0),
+                ("friend", "Jane".to_owned(), 0, 0.0, None, 1, 25.0),
+                ("friend", "John".to_owned(), 3, 75.0, Some(50.0), 0, 0.0),
+                ("level", "aji".to_owned(), 3, 75.0, Some(50.0), 1, 25.0),
+                ("level", "ki".to_owned(), 1, 25.0, None, 3, 75.0),
+            ]
+        );
+    }
+}
@@ -0,0 +1,478 @@
+use clap::ValueEnum;
+use sqlx::{Row, SqlitePool};
+use std::{collections::BTreeMap, fmt};
+
+use super::{
+    default_reduction, edit_kind, edit_level, open_pool, read_config,
+    rebuild_chances_from_memories, strategy::StrategyKind, utils, AkcConfig, DEFAULT_KINDS,
+    DEFAULT_LEVELS,
+};
+
+const MIN_CHANCE_KEY: &str = "min-chance";
+const CHANCE_MODEL_KEY: &str = "chance-model";
+const RECOVERY_HALF_LIFE_KEY: &str = "recovery-half-life";
+const SKIP_PENALTY_KEY: &str = "skip-penalty";
+const STRATEGY_KEY: &str = "strategy";
+const SOFTMAX_TEMPERATURE_KEY: &str = "softmax-temperature";
+const DEFAULT_RECOVERY_HALF_LIFE: f64 = 30.0;
+const DEFAULT_SOFTMAX_TEMPERATURE: f64 = 10.0;
+const LEVEL_CHANCE_PREFIX: &str = "chance.";
+const KIND_REDUCTION_PREFIX: &str = "reduction.";
+
+/// How chances evolve between memories.
+#[derive(ValueEnum, Clone, Copy, Debug, Default, PartialEq)]
+pub enum ChanceModel {
+    /// Chances only move when memories are recorded.
+    #[default]
+    ZeroSum,
+    /// Chances also drift back to the level base chance as time passes since the last memory.
+    Recovery,
+}
+
+impl fmt::Display for ChanceModel {
+    /// Writes the name the model is given in settings.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.to_possible_value()
+            .map_or(Ok(()), |value| f.write_str(value.get_name()))
+    }
+}
+
+/// Quoted names of every value of `T`, for error messages.
+fn value_names<T: ValueEnum + fmt::Display>() -> String {
+    T::value_variants()
+        .iter()
+        .map(|value| format!("\"{value}\""))
+        .collect::<Vec<String>>()
+        .join(", ")
+}
+
+/// Scalar settings persisted in the `settings` table.
+pub struct Settings {
+    pub min_chance: f64,
+    pub chance_model: ChanceModel,
+    /// Days after which half of the distance to the base chance is recovered.
+    pub recovery_half_life: f64,
+    /// Chance taken from a friend for each skipped suggestion since the latest memory with
+    /// them.
+    pub skip_penalty: f64,
+    /// Strategy used by `suggest` unless another one is given.
+    pub strategy: StrategyKind,
+    /// How far softmax flattens differences in chance, higher is more uniform.
+    pub softmax_temperature: f64,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            min_chance: default_reduction::TEXT,
+            chance_model: ChanceModel::default(),
+            recovery_half_life: DEFAULT_RECOVERY_HALF_LIFE,
+            skip_penalty: 0.0,
+            strategy: StrategyKind::default(),
+            softmax_temperature: DEFAULT_SOFTMAX_TEMPERATURE,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+enum DefaultKey {
+    LevelChance(String),
+    KindReduction(String),
+    MinChance,
+    ChanceModel,
+    RecoveryHalfLife,
+    SkipPenalty,
+    Strategy,
+    SoftmaxTemperature,
+}
+
+impl DefaultKey {
+    /// Key of the row in the `settings` table, for defaults which are stored there.
+    fn settings_key(&self) -> Option<&'static str> {
+        match self {
+            DefaultKey::MinChance => Some(MIN_CHANCE_KEY),
+            DefaultKey::ChanceModel => Some(CHANCE_MODEL_KEY),
+            DefaultKey::RecoveryHalfLife => Some(RECOVERY_HALF_LIFE_KEY),
+            DefaultKey::SkipPenalty => Some(SKIP_PENALTY_KEY),
+            DefaultKey::Strategy => Some(STRATEGY_KEY),
+            DefaultKey::SoftmaxTemperature => Some(SOFTMAX_TEMPERATURE_KEY),
+            DefaultKey::LevelChance(_) | DefaultKey::KindReduction(_) => None,
+        }
+    }
+}
+
+fn parse_default_key(key: &str) -> Option<DefaultKey> {
+    match key {
+        MIN_CHANCE_KEY => return Some(DefaultKey::MinChance),
+        CHANCE_MODEL_KEY => return Some(DefaultKey::ChanceModel),
+        RECOVERY_HALF_LIFE_KEY => return Some(DefaultKey::RecoveryHalfLife),
+        SKIP_PENALTY_KEY => return Some(DefaultKey::SkipPenalty),
+        STRATEGY_KEY => return Some(DefaultKey::Strategy),
+        SOFTMAX_TEMPERATURE_KEY => return Some(DefaultKey::SoftmaxTemperature),
+        _ => {}
+    }
+    if let Some(level) = key.strip_prefix(LEVEL_CHANCE_PREFIX) {
+        return (!level.is_empty()).then(|| DefaultKey::LevelChance(level.to_owned()));
+    }
+    if let Some(kind) = key.strip_prefix(KIND_REDUCTION_PREFIX) {
+        return (!kind.is_empty()).then(|| DefaultKey::KindReduction(kind.to_owned()));
+    }
+    None
+}
+
+pub(super) async fn read_settings(pool: &SqlitePool) -> Result<Settings, sqlx::Error> {
+    let rows = sqlx::query("SELECT key, value FROM settings")
+        .fetch_all(pool)
+        .await?;
+    let mut settings = Settings::default();
+    for row in rows {
+        let key: String = row.get("key");
+        let value: String = row.get("value");
+        match key.as_str() {
+            MIN_CHANCE_KEY => {
+                if let Ok(min_chance) = value.parse() {
+                    settings.min_chance = min_chance;
+                }
+            }
+            CHANCE_MODEL_KEY => {
+                if let Ok(chance_model) = ChanceModel::from_str(&value, false) {
+                    settings.chance_model = chance_model;
+                }
+            }
+            RECOVERY_HALF_LIFE_KEY => {
+                if let Ok(recovery_half_life) = value.parse() {
+                    settings.recovery_half_life = recovery_half_life;
+                }
+            }
+            SKIP_PENALTY_KEY => {
+                if let Ok(skip_penalty) = value.parse() {
+                    settings.skip_penalty = skip_penalty;
+                }
+            }
+            STRATEGY_KEY => {
+                if let Ok(strategy) = StrategyKind::from_str(&value, false) {
+                    settings.strategy = strategy;
+                }
+            }
+            SOFTMAX_TEMPERATURE_KEY => {
+                if let Ok(softmax_temperature) = value.parse() {
+                    settings.softmax_temperature = softmax_temperature;
+                }
+            }
+            _ => {}
+        }
+    }
+    Ok(settings)
+}
+
+/// Reads the raw values of all stored settings.
+pub(super) async fn read_setting_values(
+    pool: &SqlitePool,
+) -> Result<BTreeMap<String, String>, sqlx::Error> {
+    let rows = sqlx::query("SELECT key, value FROM settings")
+        .fetch_all(pool)
+        .await?;
+    Ok(rows
+        .into_iter()
+        .map(|row| (row.get("key"), row.get("value")))
+        .collect())
+}
+
+async fn write_setting(key: &str, value: &str) -> Result<(), sqlx::Error> {
+    let pool = open_pool().await?;
+    sqlx::query("INSERT OR REPLACE INTO settings (key, value) VALUES (?1, ?2)")
+        .bind(key)
+        .bind(value)
+        .execute(&pool)
+        .await?;
+    Ok(())
+}
+
+async fn delete_settings(key: Option<&str>) -> Result<(), sqlx::Error> {
+    let pool = open_pool().await?;
+    match key {
+        Some(key) => {
+            sqlx::query("DELETE FROM settings WHERE key = ?1")
+                .bind(key)
+                .execute(&pool)
+                .await?
+        }
+        None => sqlx::query("DELETE FROM settings").execute(&pool).await?,
+    };
+    Ok(())
+}
+
+async fn restore_builtin_defaults(
+    config: &AkcConfig,
+    key: Option<&DefaultKey>,
+) -> Result<(), sqlx::Error> {
+    let pool = open_pool().await?;
+    let mut transaction = pool.begin().await?;
+    for (name, chance) in DEFAULT_LEVELS {
+        let selected = match key {
+            Some(DefaultKey::LevelChance(level)) => level == name,
+            Some(_) => false,
+            None => true,
+        };
+        if selected && utils::level_chance(config, name).is_some() {
+            sqlx::query("UPDATE levels SET chance = ?1 WHERE name = ?2")
+                .bind(chance)
+                .bind(name)
+                .execute(&mut *transaction)
+                .await?;
+        }
+    }
+    for (name, reduction) in DEFAULT_KINDS {
+        let selected = match key {
+            Some(DefaultKey::KindReduction(kind)) => kind == name,
+            Some(_) => false,
+            None => true,
+        };
+        if selected && utils::kind_reduction(config, name).is_some() {
+            sqlx::query("UPDATE memory_kinds SET reduction = ?1 WHERE name = ?2")
+                .bind(reduction)
+                .bind(name)
+                .execute(&mut *transaction)
+                .await?;
+        }
+    }
+    transaction.commit().await
+}
+
+/// Prints base chances, reductions and other tunable defaults.
+pub async fn show_defaults() {
+    let config = match read_config().await {
+        Ok(config) => config,
+        Err(err) => {
+            eprintln!("Failed to read data: {err}");
+            return;
+        }
+    };
+
+    println!("{}", utils::list_defaults(&config));
+}
+
+/// Checks a value for a key of the `settings` table the way `settings set` does, and
+/// returns it as it is stored.
+pub(super) fn parse_setting_value(key: &str, value: &str) -> Result<String, String> {
+    match key {
+        CHANCE_MODEL_KEY => {
+            return ChanceModel::from_str(value, false)
+                .map(|chance_model| chance_model.to_string())
+                .map_err(|_| {
+                    format!(
+                        "Chance model must be one of {}",
+                        value_names::<ChanceModel>()
+                    )
+                });
+        }
+        STRATEGY_KEY => {
+            return StrategyKind::from_str(value, false)
+                .map(|strategy| strategy.to_string())
+                .map_err(|_| format!("Strategy must be one of {}", value_names::<StrategyKind>()));
+        }
+        MIN_CHANCE_KEY | RECOVERY_HALF_LIFE_KEY | SKIP_PENALTY_KEY | SOFTMAX_TEMPERATURE_KEY => {}
+        _ => return Err(format!("Unknown key \"{key}\"")),
+    }
+
+    let Ok(number) = value.parse::<f64>() else {
+        return Err(format!("Value of \"{key}\" must be a number"));
+    };
+    let (is_valid, message) = match key {
+        MIN_CHANCE_KEY => (
+            number >= 0.0,
+            "Minimum chance must be a non-negative number",
+        ),
+        RECOVERY_HALF_LIFE_KEY => (
+            number > 0.0,
+            "Recovery half-life must be a positive number of days",
+        ),
+        SKIP_PENALTY_KEY => (number >= 0.0, "Skip penalty must be a non-negative number"),
+        _ => (
+            number > 0.0,
+            "Softmax temperature must be a positive number",
+        ),
+    };
+    if !(number.is_finite() && is_valid) {
+        return Err(message.to_owned());
+    }
+    Ok(number.to_string())
+}
+
+/// Changes a single default, replaying history when it affects chances.
+pub async fn set_default(key: String, value: String) {
+    let Some(parsed_key) = parse_default_key(&key) else {
+        println!("Unknown key \"{key}\"");
+        return;
+    };
+    if let Some(settings_key) = parsed_key.settings_key() {
+        match parse_setting_value(settings_key, &value) {
+            Ok(value) => {
+                if let Err(err) = write_setting(settings_key, &value).await {
+                    eprintln!("Failed to write data: {err}");
+                }
+            }
+            Err(message) => println!("{message}"),
+        }
+        return;
+    }
+
+    let Ok(value) = value.parse::<f64>() else {
+        println!("Value of \"{key}\" must be a number");
+        return;
+    };
+    match parsed_key {
+        DefaultKey::LevelChance(level) => edit_level(level, None, Some(value)).await,
+        DefaultKey::KindReduction(kind) => edit_kind(kind, None, Some(value)).await,
+        _ => {}
+    }
+}
+
+/// Restores one or all defaults to their built-in values.
+pub async fn reset_defaults(key: Option<String>) {
+    let parsed_key = match key {
+        Some(ref key) => match parse_default_key(key) {
+            Some(parsed_key) => Some(parsed_key),
+            None => {
+                println!("Unknown key \"{key}\"");
+                return;
+            }
+        },
+        None => None,
+    };
+
+    let config = match read_config().await {
+        Ok(config) => config,
+        Err(err) => {
+            eprintln!("Failed to read data: {err}");
+            return;
+        }
+    };
+
+    match parsed_key {
+        Some(DefaultKey::LevelChance(ref level))
+            if utils::level_chance(&config, level).is_none() =>
+        {
+            println!("Level \"{level}\" not found");
+            return;
+        }
+        Some(DefaultKey::KindReduction(ref kind))
+            if utils::kind_reduction(&config, kind).is_none() =>
+        {
+            println!("Memory kind \"{kind}\" not found");
+            return;
+        }
+        Some(DefaultKey::LevelChance(ref level))
+            if !DEFAULT_LEVELS.iter().any(|(name, _)| name == level) =>
+        {
+            println!("Level \"{level}\" has no built-in default");
+            return;
+        }
+        Some(DefaultKey::KindReduction(ref kind))
+            if !DEFAULT_KINDS.iter().any(|(name, _)| name == kind) =>
+        {
+            println!("Memory kind \"{kind}\" has no built-in default");
+            return;
+        }
+        _ => {}
+    }
+
+    let settings_key = parsed_key.as_ref().and_then(DefaultKey::settings_key);
+    if parsed_key.is_none() || settings_key.is_some() {
+        if let Err(err) = delete_settings(settings_key).await {
+            eprintln!("Failed to write data: {err}");
+            return;
+        }
+    }
+
+    if settings_key.is_some() {
+        return;
+    }
+    if let Err(err) = restore_builtin_defaults(&config, parsed_key.as_ref()).await {
+        eprintln!("Failed to write data: {err}");
+        return;
+    }
+    if let Err(err) = rebuild_chances_from_memories().await {
+        eprintln!("Failed to rebuild chances: {err}");
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use clap::ValueEnum;
+
+    use super::{parse_default_key, parse_setting_value, value_names, ChanceModel, DefaultKey};
+
+    #[test]
+    fn test_parse_chance_model() {
+        assert_eq!(
+            ChanceModel::from_str("recovery", false),
+            Ok(ChanceModel::Recovery)
+        );
+        assert_eq!(ChanceModel::ZeroSum.to_string(), "zero-sum");
+        assert!(ChanceModel::from_str("decay", false).is_err());
+        assert_eq!(value_names::<ChanceModel>(), "\"zero-sum\", \"recovery\"");
+    }
+
+    #[test]
+    fn test_parse_default_key() {
+        assert_eq!(parse_default_key("min-chance"), Some(DefaultKey::MinChance));
+        assert_eq!(
+            parse_default_key("skip-penalty"),
+            Some(DefaultKey::SkipPenalty)
+        );
+        assert_eq!(parse_default_key("strategy"), Some(DefaultKey::Strategy));
+        assert_eq!(
+            parse_default_key("softmax-temperature"),
+            Some(DefaultKey::SoftmaxTemperature)
+        );
+        assert_eq!(
+            parse_default_key("chance-model"),
+            Some(DefaultKey::ChanceModel)
+        );
+        assert_eq!(
+            parse_default_key("chance.aji"),
+            Some(DefaultKey::LevelChance("aji".to_owned()))
+        );
+        assert_eq!(
+            parse_default_key("reduction.game night"),
+            Some(DefaultKey::KindReduction("game night".to_owned()))
+        );
+        assert_eq!(parse_default_key("chance."), None);
+        assert_eq!(parse_default_key("aji"), None);
+    }
+
+    #[test]
+    fn test_parse_setting_value() {
+        assert_eq!(
+            parse_setting_value("min-chance", "0.5"),
+            Ok("0.5".to_owned())
+        );
+        assert_eq!(parse_setting_value("skip-penalty", "0"), Ok("0".to_owned()));
+        assert_eq!(
+            parse_setting_value("strategy", "softmax"),
+            Ok("softmax".to_owned())
+        );
+        assert_eq!(
+            parse_setting_value("chance-model", "recovery"),
+            Ok("recovery".to_owned())
+        );
+        assert_eq!(
+            parse_setting_value("recovery-half-life", "0"),
+            Err("Recovery half-life must be a positive number of days".to_owned())
+        );
+        assert_eq!(
+            parse_setting_value("softmax-temperature", "warm"),
+            Err("Value of \"softmax-temperature\" must be a number".to_owned())
+        );
+        assert_eq!(
+            parse_setting_value("min-chance", "NaN"),
+            Err("Minimum chance must be a non-negative number".to_owned())
+        );
+        assert!(parse_setting_value("chance-model", "decay").is_err());
+        assert_eq!(
+            parse_setting_value("chance.aji", "1"),
+            Err("Unknown key \"chance.aji\"".to_owned())
+        );
+    }
+}
@@ -0,0 +1,117 @@
+use serde::Serialize;
+use sqlx::Row;
+use std::collections::{BTreeMap, HashMap};
+
+use super::{
+    open_pool,
+    output::{self, OutputFormat},
+    read_suggestion_context, strategy, utils,
+};
+
+/// Connection status of a single friend.
+#[derive(Serialize)]
+pub struct FriendStatus {
+    pub name: String,
+    pub level: String,
+    pub chance: f64,
+    /// Percentage probability of being picked by the next suggestion.
+    pub probability: f64,
+    pub last_memory_date: Option<String>,
+    pub last_memory_kind: Option<String>,
+    /// Number of memories per kind.
+    pub memory_counts: BTreeMap<String, i64>,
+}
+
+async fn read_last_memories() -> Result<HashMap<String, (String, String)>, sqlx::Error> {
+    let pool = open_pool().await?;
+    let rows = sqlx::query(
+        "SELECT friends.name, date(memories.created_at) AS date, memories.kind
+            FROM friends
+            JOIN memory_participants ON memory_participants.friend_id = friends.id
+            JOIN memories ON memories.id = memory_participants.memory_id
+            ORDER BY memories.created_at DESC, memories.id DESC",
+    )
+    .fetch_all(&pool)
+    .await?;
+    let mut last_memories = HashMap::new();
+    for row in rows {
+        last_memories
+            .entry(row.get("name"))
+            .or_insert_with(|| (row.get("date"), row.get("kind")));
+    }
+    Ok(last_memories)
+}
+
+async fn read_memory_counts() -> Result<HashMap<String, BTreeMap<String, i64>>, sqlx::Error> {
+    let pool = open_pool().await?;
+    let rows = sqlx::query(
+        "SELECT friends.name, memories.kind, COUNT(*) AS count
+            FROM friends
+            JOIN memory_participants ON memory_participants.friend_id = friends.id
+            JOIN memories ON memories.id = memory_participants.memory_id
+            GROUP BY friends.id, memories.kind",
+    )
+    .fetch_all(&pool)
+    .await?;
+    let mut memory_counts: HashMap<String, BTreeMap<String, i64>> = HashMap::new();
+    for row in rows {
+        memory_counts
+            .entry(row.get("name"))
+            .or_default()
+            .insert(row.get("kind"), row.get("count"));
+    }
+    Ok(memory_counts)
+}
+
+/// Prints level, chance, suggestion probability and memory history of every friend.
+pub async fn print_status(format: OutputFormat) {
+    let context = match read_suggestion_context().await {
+        Ok(context) => context,
+        Err(err) => {
+            eprintln!("Failed to read data: {err}");
+            return;
+        }
+    };
+    let mut last_memories = match read_last_memories().await {
+        Ok(last_memories) => last_memories,
+        Err(err) => {
+            eprintln!("Failed to read memories: {err}");
+            return;
+        }
+    };
+    let mut memory_counts = match read_memory_counts().await {
+        Ok(memory_counts) => memory_counts,
+        Err(err) => {
+            eprintln!("Failed to read memories: {err}");
+            return;
+        }
+    };
+
+    let probabilities =
+        strategy::next_pick_probabilities(&*context.strategy(None, None), &context.candidates());
+    let statuses = context
+        .config
+        .friends
+        .iter()
+        .map(|friend| {
+            let (last_memory_date, last_memory_kind) = last_memories
+                .remove(&friend.name)
+                .map_or((None, None), |(date, kind)| (Some(date), Some(kind)));
+            FriendStatus {
+                name: friend.name.clone(),
+                level: friend.level.clone(),
+                chance: friend.chance,
+                probability: probabilities.get(&friend.name).copied().unwrap_or(0.0) * 100.0,
+                last_memory_date,
+                last_memory_kind,
+                memory_counts: memory_counts.remove(&friend.name).unwrap_or_default(),
+            }
+        })
+        .collect::<Vec<FriendStatus>>();
+
+    let rendered_status = match format {
+        OutputFormat::Text | OutputFormat::Table => utils::render_status(statuses),
+        _ => output::render(format, &statuses),
+    };
+    println!("{rendered_status}");
+}
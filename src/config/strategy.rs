@@ -0,0 +1,275 @@
+use clap::ValueEnum;
+use std::{collections::HashMap, fmt};
+
+use super::{utils, AkcConfig, FriendInfo};
+
+/// Which strategy picks suggested friends.
+#[derive(ValueEnum, Clone, Copy, Debug, Default, PartialEq)]
+pub enum StrategyKind {
+    /// Pick in proportion to chance
+    #[default]
+    WeightedRandom,
+    /// Pick whoever you haven't shared a memory with for the longest time
+    LongestSinceContact,
+    /// Take turns between levels, picking by chance within a level
+    RoundRobin,
+    /// Pick in proportion to the exponential of chance divided by a temperature
+    Softmax,
+}
+
+impl fmt::Display for StrategyKind {
+    /// Writes the name the strategy is given on the command line and in settings.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.to_possible_value()
+            .map_or(Ok(()), |value| f.write_str(value.get_name()))
+    }
+}
+
+/// Decides how likely each candidate is to be suggested next.
+pub trait SuggestionStrategy {
+    /// Non-negative weight of each candidate, in the same order. Candidates are drawn in
+    /// proportion to their weight.
+    fn weights(&self, candidates: &[&FriendInfo]) -> Vec<f64>;
+
+    /// Called with every drawn friend, for strategies which depend on earlier picks.
+    fn record_pick(&mut self, _friend: &FriendInfo) {}
+}
+
+pub(super) struct WeightedRandom;
+
+impl SuggestionStrategy for WeightedRandom {
+    fn weights(&self, candidates: &[&FriendInfo]) -> Vec<f64> {
+        candidates.iter().map(|friend| friend.chance).collect()
+    }
+}
+
+struct LongestSinceContact {
+    /// Friends without any memory count as never contacted.
+    days_since_last_memory: HashMap<String, f64>,
+}
+
+impl SuggestionStrategy for LongestSinceContact {
+    fn weights(&self, candidates: &[&FriendInfo]) -> Vec<f64> {
+        let days = candidates
+            .iter()
+            .map(|friend| {
+                self.days_since_last_memory
+                    .get(&friend.name)
+                    .copied()
+                    .unwrap_or(f64::INFINITY)
+            })
+            .collect::<Vec<f64>>();
+        let longest = days.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+        // Ties are broken at random.
+        days.iter()
+            .map(|&days| if days == longest { 1.0 } else { 0.0 })
+            .collect()
+    }
+}
+
+struct RoundRobin {
+    /// Level names in the order they take turns.
+    levels: Vec<String>,
+    /// Level of the friend suggested last.
+    last_level: Option<String>,
+}
+
+impl RoundRobin {
+    /// First level after the last one which has a candidate with a positive chance.
+    fn next_level(&self, candidates: &[&FriendInfo]) -> Option<&String> {
+        let start = self
+            .last_level
+            .as_ref()
+            .and_then(|last_level| self.levels.iter().position(|level| level == last_level))
+            .map_or(0, |index| index + 1);
+        (0..self.levels.len())
+            .map(|offset| &self.levels[(start + offset) % self.levels.len()])
+            .find(|level| {
+                candidates
+                    .iter()
+                    .any(|friend| &friend.level == *level && friend.chance > 0.0)
+            })
+    }
+}
+
+impl SuggestionStrategy for RoundRobin {
+    fn weights(&self, candidates: &[&FriendInfo]) -> Vec<f64> {
+        let level = self.next_level(candidates);
+        candidates
+            .iter()
+            .map(|friend| match level {
+                Some(level) if &friend.level == level => friend.chance,
+                _ => 0.0,
+            })
+            .collect()
+    }
+
+    fn record_pick(&mut self, friend: &FriendInfo) {
+        self.last_level = Some(friend.level.clone());
+    }
+}
+
+struct Softmax {
+    temperature: f64,
+}
+
+impl SuggestionStrategy for Softmax {
+    fn weights(&self, candidates: &[&FriendInfo]) -> Vec<f64> {
+        // Shifting by the highest chance keeps the exponentials from overflowing.
+        let highest = candidates
+            .iter()
+            .map(|friend| friend.chance)
+            .fold(f64::NEG_INFINITY, f64::max);
+        candidates
+            .iter()
+            .map(|friend| ((friend.chance - highest) / self.temperature).exp())
+            .collect()
+    }
+}
+
+/// Builds a strategy of the given kind. `last_level` is the level of the latest stored
+/// suggestion, where round-robin continues from.
+pub(super) fn build_strategy(
+    kind: StrategyKind,
+    config: &AkcConfig,
+    days_since_last_memory: &HashMap<String, f64>,
+    temperature: f64,
+    last_level: Option<String>,
+) -> Box<dyn SuggestionStrategy> {
+    match kind {
+        StrategyKind::WeightedRandom => Box::new(WeightedRandom),
+        StrategyKind::LongestSinceContact => Box::new(LongestSinceContact {
+            days_since_last_memory: days_since_last_memory.clone(),
+        }),
+        StrategyKind::RoundRobin => Box::new(RoundRobin {
+            levels: utils::sort_levels(config)
+                .iter()
+                .map(|level| level.name.clone())
+                .collect(),
+            last_level,
+        }),
+        StrategyKind::Softmax => Box::new(Softmax { temperature }),
+    }
+}
+
+/// Probability of each candidate being picked next by `strategy`.
+pub(super) fn probabilities(
+    strategy: &dyn SuggestionStrategy,
+    candidates: &[&FriendInfo],
+) -> HashMap<String, f64> {
+    let weights = strategy.weights(candidates);
+    let total_weight = weights.iter().sum::<f64>();
+    candidates
+        .iter()
+        .zip(weights)
+        .map(|(friend_info, weight)| {
+            let probability = if total_weight <= f64::EPSILON {
+                0.0
+            } else {
+                weight / total_weight
+            };
+            (friend_info.name.clone(), probability)
+        })
+        .collect()
+}
+
+/// Probability of each candidate being picked next by `strategy`, drawing from the first of
+/// `tiers` which has a candidate of positive weight, like `utils::sample_without_replacement`.
+pub(super) fn next_pick_probabilities(
+    strategy: &dyn SuggestionStrategy,
+    tiers: &[Vec<&FriendInfo>],
+) -> HashMap<String, f64> {
+    tiers
+        .iter()
+        .find(|tier| strategy.weights(tier).iter().sum::<f64>() > f64::EPSILON)
+        .map(|tier| probabilities(strategy, tier))
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod test {
+    use clap::ValueEnum;
+    use std::collections::HashMap;
+
+    use super::{
+        probabilities, LongestSinceContact, RoundRobin, Softmax, StrategyKind, SuggestionStrategy,
+        WeightedRandom,
+    };
+    use crate::config::{friend_info, FriendInfo};
+
+    #[test]
+    fn test_parse_strategy_kind() {
+        for kind in StrategyKind::value_variants() {
+            assert_eq!(StrategyKind::from_str(&kind.to_string(), false), Ok(*kind));
+        }
+        assert_eq!(StrategyKind::RoundRobin.to_string(), "round-robin");
+        assert!(StrategyKind::from_str("random", false).is_err());
+    }
+
+    #[test]
+    fn test_weighted_random_probabilities() {
+        let john = friend_info("John", "aji", 3.0);
+        let doe = friend_info("Doe", "ki", 1.0);
+
+        let probabilities = probabilities(&WeightedRandom, &[&john, &doe]);
+        assert_eq!(probabilities["John"], 0.75);
+        assert_eq!(probabilities["Doe"], 0.25);
+    }
+
+    #[test]
+    fn test_longest_since_contact_weights() {
+        let friends = [
+            friend_info("John", "aji", 3.0),
+            friend_info("Doe", "ki", 1.0),
+            friend_info("Jane", "ki", 1.0),
+        ];
+        let candidates = friends.iter().collect::<Vec<&FriendInfo>>();
+        let mut strategy = LongestSinceContact {
+            days_since_last_memory: HashMap::from([
+                ("John".to_owned(), 40.0),
+                ("Doe".to_owned(), 2.0),
+                ("Jane".to_owned(), 40.0),
+            ]),
+        };
+
+        assert_eq!(strategy.weights(&candidates), vec![1.0, 0.0, 1.0]);
+        strategy.days_since_last_memory.remove("Doe");
+        assert_eq!(strategy.weights(&candidates), vec![0.0, 1.0, 0.0]);
+    }
+
+    #[test]
+    fn test_round_robin_weights() {
+        let friends = [
+            friend_info("John", "aji", 3.0),
+            friend_info("Doe", "ki", 1.0),
+            friend_info("Ali", "chi", 0.0),
+        ];
+        let candidates = friends.iter().collect::<Vec<&FriendInfo>>();
+        let mut strategy = RoundRobin {
+            levels: vec!["aji".to_owned(), "ki".to_owned(), "chi".to_owned()],
+            last_level: None,
+        };
+
+        assert_eq!(strategy.weights(&candidates), vec![3.0, 0.0, 0.0]);
+        strategy.record_pick(&friends[0]);
+        assert_eq!(strategy.weights(&candidates), vec![0.0, 1.0, 0.0]);
+        // Levels without a candidate to pick are passed over.
+        strategy.record_pick(&friends[1]);
+        assert_eq!(strategy.weights(&candidates), vec![3.0, 0.0, 0.0]);
+    }
+
+    #[test]
+    fn test_softmax_weights() {
+        let friends = [
+            friend_info("John", "aji", 3.0),
+            friend_info("Doe", "ki", 1.0),
+        ];
+        let candidates = friends.iter().collect::<Vec<&FriendInfo>>();
+
+        let weights = Softmax { temperature: 2.0 }.weights(&candidates);
+        assert_eq!(weights[0], 1.0);
+        assert!((weights[1] - (-1.0f64).exp()).abs() < 1e-12);
+        let probabilities = probabilities(&Softmax { temperature: 1e6 }, &candidates);
+        assert!((probabilities["John"] - 0.5).abs() < 1e-5);
+    }
+}
@@ -0,0 +1,806 @@
+use serde::{Deserialize, Serialize};
+use sqlx::{Row, SqlitePool};
+use std::{
+    collections::{BTreeMap, HashSet},
+    fs,
+    path::Path,
+};
+
+use super::{
+    constraint::read_constraints,
+    memory::{parse_date_input, DateInput},
+    open_pool,
+    plan::{parse_plan_date, read_plans, PlanInfo},
+    read_config, read_memories, rebuild_chances_from_memories, settings, utils, AkcConfig,
+    KindInfo, LevelInfo, MemoryInfo,
+};
+
+/// Version of the export document, bumped on incompatible changes.
+const EXPORT_VERSION: i64 = 1;
+
+#[derive(Serialize, Deserialize)]
+pub(super) struct ExportedFriend {
+    pub name: String,
+    pub level: String,
+    #[serde(default)]
+    pub note: Option<String>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct ExportedMemory {
+    kind: String,
+    participants: Vec<String>,
+    created_at: String,
+    #[serde(default)]
+    note: Option<String>,
+    #[serde(default)]
+    place: Option<String>,
+    #[serde(default)]
+    tags: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct ExportedPlan {
+    kind: String,
+    participants: Vec<String>,
+    planned_on: String,
+    #[serde(default)]
+    note: Option<String>,
+}
+
+/// A friend or level constraint.
+#[derive(Serialize, Deserialize)]
+struct ExportedConstraint {
+    /// `friend` or `level`.
+    target: String,
+    name: String,
+    #[serde(default)]
+    cooldown_days: Option<i64>,
+    #[serde(default)]
+    interval_days: Option<i64>,
+}
+
+/// Everything needed to recreate the database elsewhere. Chances are not exported since
+/// they are rebuilt from memories.
+#[derive(Serialize, Deserialize)]
+struct ExportDocument {
+    version: i64,
+    levels: Vec<LevelInfo>,
+    kinds: Vec<KindInfo>,
+    friends: Vec<ExportedFriend>,
+    memories: Vec<ExportedMemory>,
+    #[serde(default)]
+    plans: Vec<ExportedPlan>,
+    #[serde(default)]
+    constraints: Vec<ExportedConstraint>,
+    #[serde(default)]
+    settings: BTreeMap<String, String>,
+}
+
+/// How imported data is combined with existing data.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ImportMode {
+    /// Keep existing data and add what's missing.
+    Merge,
+    /// Delete existing data first.
+    Replace,
+}
+
+pub(super) async fn read_exported_friends(
+    pool: &SqlitePool,
+) -> Result<Vec<ExportedFriend>, sqlx::Error> {
+    let rows = sqlx::query(
+        "SELECT name, level, note,
+            (SELECT group_concat(tag, char(10)) FROM friend_tags
+                WHERE friend_tags.friend_id = friends.id) AS tags
+            FROM friends
+            ORDER BY id ASC",
+    )
+    .fetch_all(pool)
+    .await?;
+    let friends = rows
+        .into_iter()
+        .map(|row| {
+            let tags: Option<String> = row.get("tags");
+            let mut tags = tags
+                .map(|tags| tags.lines().map(str::to_owned).collect::<Vec<String>>())
+                .unwrap_or_default();
+            tags.sort();
+            ExportedFriend {
+                name: row.get("name"),
+                level: row.get("level"),
+                note: row.get("note"),
+                tags,
+            }
+        })
+        .collect();
+    Ok(friends)
+}
+
+async fn read_export_document() -> Result<ExportDocument, sqlx::Error> {
+    let config = read_config().await?;
+    let memories = read_memories().await?;
+    let plans = read_plans().await?;
+    let constraints = read_constraints().await?;
+    let pool = open_pool().await?;
+    let friends = read_exported_friends(&pool).await?;
+    let settings = settings::read_setting_values(&pool).await?;
+
+    Ok(ExportDocument {
+        version: EXPORT_VERSION,
+        levels: config.levels,
+        kinds: config.kinds,
+        friends,
+        memories: memories
+            .into_iter()
+            .map(|memory| ExportedMemory {
+                kind: memory.kind,
+                participants: memory.names,
+                created_at: memory.created_at,
+                note: memory.note,
+                place: memory.place,
+                tags: memory.tags,
+            })
+            .collect(),
+        plans: plans
+            .into_iter()
+            .map(|plan| ExportedPlan {
+                kind: plan.kind,
+                participants: plan.participants,
+                planned_on: plan.planned_on,
+                note: plan.note,
+            })
+            .collect(),
+        constraints: utils::constraint_records(&constraints)
+            .into_iter()
+            .map(|record| ExportedConstraint {
+                target: record.target.to_owned(),
+                name: record.name,
+                cooldown_days: record.cooldown_days,
+                interval_days: record.interval_days,
+            })
+            .collect(),
+        settings,
+    })
+}
+
+/// Collects every problem that would make importing `document` inconsistent. In merge mode,
+/// names may also refer to levels, kinds and friends of `existing`.
+fn validate_document(
+    document: &ExportDocument,
+    existing: &AkcConfig,
+    mode: ImportMode,
+    is_reserved_level: fn(&str) -> bool,
+    is_reserved_kind: fn(&str) -> bool,
+) -> Vec<String> {
+    let mut errors = Vec::new();
+    if document.version > EXPORT_VERSION {
+        errors.push(format!(
+            "Version {} is not supported, the latest supported version is {EXPORT_VERSION}",
+            document.version
+        ));
+        return errors;
+    }
+
+    let mut level_names = HashSet::new();
+    let mut kind_names = HashSet::new();
+    let mut friend_names = HashSet::new();
+    if mode == ImportMode::Merge {
+        level_names.extend(existing.levels.iter().map(|level| level.name.as_str()));
+        kind_names.extend(existing.kinds.iter().map(|kind| kind.name.as_str()));
+        friend_names.extend(existing.friends.iter().map(|friend| friend.name.as_str()));
+    }
+
+    let mut seen_names = HashSet::new();
+    for level in &document.levels {
+        if !seen_names.insert(&level.name) {
+            errors.push(format!("Level \"{}\" is duplicated", level.name));
+        }
+        if is_reserved_level(&level.name) {
+            errors.push(format!("Level name \"{}\" is reserved", level.name));
+        }
+        if !(level.chance.is_finite() && level.chance > 0.0) {
+            errors.push(format!(
+                "Chance of level \"{}\" must be positive",
+                level.name
+            ));
+        }
+        level_names.insert(&level.name);
+    }
+    let mut seen_names = HashSet::new();
+    for kind in &document.kinds {
+        if !seen_names.insert(&kind.name) {
+            errors.push(format!("Memory kind \"{}\" is duplicated", kind.name));
+        }
+        if is_reserved_kind(&kind.name) {
+            errors.push(format!("Memory kind name \"{}\" is reserved", kind.name));
+        }
+        if !(kind.reduction.is_finite() && kind.reduction > 0.0) {
+            errors.push(format!(
+                "Reduction of memory kind \"{}\" must be positive",
+                kind.name
+            ));
+        }
+        kind_names.insert(&kind.name);
+    }
+    let mut seen_names = HashSet::new();
+    for friend in &document.friends {
+        if friend.name.trim().is_empty() {
+            errors.push("Friend names can't be empty".to_owned());
+        }
+        if !seen_names.insert(&friend.name) {
+            errors.push(format!("Friend \"{}\" is duplicated", friend.name));
+        }
+        if !level_names.contains(friend.level.as_str()) {
+            errors.push(format!(
+                "Level \"{}\" of friend \"{}\" not found",
+                friend.level, friend.name
+            ));
+        }
+        friend_names.insert(&friend.name);
+    }
+    for (index, memory) in document.memories.iter().enumerate() {
+        let number = index + 1;
+        if !kind_names.contains(memory.kind.as_str()) {
+            errors.push(format!(
+                "Memory #{number}: memory kind \"{}\" not found",
+                memory.kind
+            ));
+        }
+        if memory.participants.is_empty() {
+            errors.push(format!("Memory #{number}: no participants"));
+        }
+        for name in &memory.participants {
+            if !friend_names.contains(name.as_str()) {
+                errors.push(format!("Memory #{number}: friend \"{name}\" not found"));
+            }
+        }
+        let is_valid_timestamp = matches!(
+            parse_date_input(&memory.created_at),
+            Some(DateInput::Absolute(ref datetime)) if *datetime == memory.created_at
+        );
+        if !is_valid_timestamp {
+            errors.push(format!(
+                "Memory #{number}: date \"{}\" must be formatted as YYYY-MM-DD HH:MM:SS",
+                memory.created_at
+            ));
+        }
+    }
+    for (index, plan) in document.plans.iter().enumerate() {
+        let number = index + 1;
+        if !kind_names.contains(plan.kind.as_str()) {
+            errors.push(format!(
+                "Plan #{number}: memory kind \"{}\" not found",
+                plan.kind
+            ));
+        }
+        if plan.participants.is_empty() {
+            errors.push(format!("Plan #{number}: no participants"));
+        }
+        for name in &plan.participants {
+            if !friend_names.contains(name.as_str()) {
+                errors.push(format!("Plan #{number}: friend \"{name}\" not found"));
+            }
+        }
+        if parse_plan_date(&plan.planned_on).as_ref() != Some(&plan.planned_on) {
+            errors.push(format!(
+                "Plan #{number}: date \"{}\" must be formatted as YYYY-MM-DD or YYYY-MM-DD HH:MM:SS",
+                plan.planned_on
+            ));
+        }
+    }
+    for constraint in &document.constraints {
+        let target_found = match constraint.target.as_str() {
+            "friend" => friend_names.contains(constraint.name.as_str()),
+            "level" => level_names.contains(constraint.name.as_str()),
+            target => {
+                errors.push(format!(
+                    "Constraint target \"{target}\" must be \"friend\" or \"level\""
+                ));
+                continue;
+            }
+        };
+        if !target_found {
+            errors.push(format!(
+                "Constraint: {} \"{}\" not found",
+                constraint.target, constraint.name
+            ));
+        }
+        let days = [constraint.cooldown_days, constraint.interval_days];
+        if days.iter().flatten().any(|days| *days < 1) {
+            errors.push(format!(
+                "Constraint of {} \"{}\": days must be positive",
+                constraint.target, constraint.name
+            ));
+        }
+    }
+    for (key, value) in &document.settings {
+        if let Err(message) = settings::parse_setting_value(key, value) {
+            errors.push(format!("Setting \"{key}\": {message}"));
+        }
+    }
+    errors
+}
+
+/// Picks the memories of `document` to import. Replacing imports all of them, merging
+/// skips those with the same kind, time and participants as an `existing` memory.
+fn memories_to_import<'a>(
+    document: &'a ExportDocument,
+    existing: &[MemoryInfo],
+    mode: ImportMode,
+) -> Vec<&'a ExportedMemory> {
+    if mode == ImportMode::Replace {
+        return document.memories.iter().collect();
+    }
+    let existing_keys = existing
+        .iter()
+        .map(|memory| utils::memory_key(&memory.kind, &memory.created_at, &memory.names))
+        .collect::<HashSet<_>>();
+    document
+        .memories
+        .iter()
+        .filter(|memory| {
+            let key = utils::memory_key(&memory.kind, &memory.created_at, &memory.participants);
+            !existing_keys.contains(&key)
+        })
+        .collect()
+}
+
+/// Picks the plans of `document` to import, like `memories_to_import` does for memories.
+fn plans_to_import<'a>(
+    document: &'a ExportDocument,
+    existing: &[PlanInfo],
+    mode: ImportMode,
+) -> Vec<&'a ExportedPlan> {
+    if mode == ImportMode::Replace {
+        return document.plans.iter().collect();
+    }
+    let existing_keys = existing
+        .iter()
+        .map(|plan| utils::memory_key(&plan.kind, &plan.planned_on, &plan.participants))
+        .collect::<HashSet<_>>();
+    document
+        .plans
+        .iter()
+        .filter(|plan| {
+            let key = utils::memory_key(&plan.kind, &plan.planned_on, &plan.participants);
+            !existing_keys.contains(&key)
+        })
+        .collect()
+}
+
+async fn write_export_document(
+    document: &ExportDocument,
+    memories: &[&ExportedMemory],
+    plans: &[&ExportedPlan],
+    mode: ImportMode,
+) -> Result<(), sqlx::Error> {
+    let pool = open_pool().await?;
+    let mut transaction = pool.begin().await?;
+    if mode == ImportMode::Replace {
+        // Participants, tags and constraints are cascaded.
+        for table in [
+            "memory_trash",
+            "memories",
+            "plans",
+            "friends",
+            "levels",
+            "memory_kinds",
+            "settings",
+        ] {
+            sqlx::query(&format!("DELETE FROM {table}"))
+                .execute(&mut *transaction)
+                .await?;
+        }
+    }
+
+    for level in &document.levels {
+        sqlx::query("INSERT OR IGNORE INTO levels (name, chance) VALUES (?1, ?2)")
+            .bind(&level.name)
+            .bind(level.chance)
+            .execute(&mut *transaction)
+            .await?;
+    }
+    for kind in &document.kinds {
+        sqlx::query("INSERT OR IGNORE INTO memory_kinds (name, reduction) VALUES (?1, ?2)")
+            .bind(&kind.name)
+            .bind(kind.reduction)
+            .execute(&mut *transaction)
+            .await?;
+    }
+    for friend in &document.friends {
+        sqlx::query(
+            "INSERT OR IGNORE INTO friends (name, chance, level)
+                SELECT ?1, chance, name FROM levels WHERE name = ?2",
+        )
+        .bind(&friend.name)
+        .bind(&friend.level)
+        .execute(&mut *transaction)
+        .await?;
+        if friend.note.is_some() {
+            sqlx::query("UPDATE friends SET note = COALESCE(note, ?1) WHERE name = ?2")
+                .bind(&friend.note)
+                .bind(&friend.name)
+                .execute(&mut *transaction)
+                .await?;
+        }
+        for tag in &friend.tags {
+            sqlx::query(
+                "INSERT OR IGNORE INTO friend_tags (friend_id, tag)
+                    SELECT id, ?1 FROM friends WHERE name = ?2",
+            )
+            .bind(tag)
+            .bind(&friend.name)
+            .execute(&mut *transaction)
+            .await?;
+        }
+    }
+    for memory in memories {
+        let memory_id = sqlx::query(
+            "INSERT INTO memories (kind, created_at, note, place) VALUES (?1, ?2, ?3, ?4)",
+        )
+        .bind(&memory.kind)
+        .bind(&memory.created_at)
+        .bind(&memory.note)
+        .bind(&memory.place)
+        .execute(&mut *transaction)
+        .await?
+        .last_insert_rowid();
+        for name in &memory.participants {
+            sqlx::query(
+                "INSERT OR IGNORE INTO memory_participants (memory_id, friend_id)
+                    SELECT ?1, id FROM friends WHERE name = ?2",
+            )
+            .bind(memory_id)
+            .bind(name)
+            .execute(&mut *transaction)
+            .await?;
+        }
+        for tag in &memory.tags {
+            sqlx::query("INSERT OR IGNORE INTO memory_tags (memory_id, tag) VALUES (?1, ?2)")
+                .bind(memory_id)
+                .bind(tag)
+                .execute(&mut *transaction)
+                .await?;
+        }
+    }
+    for plan in plans {
+        let plan_id = sqlx::query("INSERT INTO plans (kind, planned_on, note) VALUES (?1, ?2, ?3)")
+            .bind(&plan.kind)
+            .bind(&plan.planned_on)
+            .bind(&plan.note)
+            .execute(&mut *transaction)
+            .await?
+            .last_insert_rowid();
+        for name in &plan.participants {
+            sqlx::query(
+                "INSERT OR IGNORE INTO plan_participants (plan_id, friend_id)
+                    SELECT ?1, id FROM friends WHERE name = ?2",
+            )
+            .bind(plan_id)
+            .bind(name)
+            .execute(&mut *transaction)
+            .await?;
+        }
+    }
+    // Existing constraints are kept when merging.
+    for constraint in &document.constraints {
+        let query = if constraint.target == "friend" {
+            sqlx::query(
+                "INSERT OR IGNORE INTO friend_constraints (friend_id, cooldown_days, interval_days)
+                    SELECT id, ?2, ?3 FROM friends WHERE name = ?1",
+            )
+        } else {
+            sqlx::query(
+                "INSERT OR IGNORE INTO level_constraints (level, cooldown_days, interval_days)
+                    VALUES (?1, ?2, ?3)",
+            )
+        };
+        query
+            .bind(&constraint.name)
+            .bind(constraint.cooldown_days)
+            .bind(constraint.interval_days)
+            .execute(&mut *transaction)
+            .await?;
+    }
+    for (key, value) in &document.settings {
+        sqlx::query("INSERT OR IGNORE INTO settings (key, value) VALUES (?1, ?2)")
+            .bind(key)
+            .bind(value)
+            .execute(&mut *transaction)
+            .await?;
+    }
+    transaction.commit().await
+}
+
+/// Writes friends, levels, memory kinds, memories, plans, constraints and settings as JSON
+/// to `out`, or to stdout if no file is given.
+pub async fn export_data(out: Option<&Path>) {
+    let document = match read_export_document().await {
+        Ok(document) => document,
+        Err(err) => {
+            eprintln!("Failed to read data: {err}");
+            return;
+        }
+    };
+    let json = match serde_json::to_string_pretty(&document) {
+        Ok(json) => json,
+        Err(err) => {
+            eprintln!("Failed to serialize data: {err}");
+            return;
+        }
+    };
+
+    match out {
+        Some(path) => {
+            if let Err(err) = fs::write(path, json + "\n") {
+                eprintln!("Failed to write {}: {err}", path.display());
+            }
+        }
+        None => println!("{json}"),
+    }
+}
+
+/// Imports a document written by `export_data`, then rebuilds chances from memories.
+/// Levels and memory kinds for which `is_reserved_level` or `is_reserved_kind` holds are
+/// refused.
+pub async fn import_data(
+    path: &Path,
+    mode: ImportMode,
+    is_reserved_level: fn(&str) -> bool,
+    is_reserved_kind: fn(&str) -> bool,
+) {
+    let json = match fs::read_to_string(path) {
+        Ok(json) => json,
+        Err(err) => {
+            eprintln!("Failed to read {}: {err}", path.display());
+            return;
+        }
+    };
+    let document = match serde_json::from_str::<ExportDocument>(&json) {
+        Ok(document) => document,
+        Err(err) => {
+            println!("{} is not a valid export: {err}", path.display());
+            return;
+        }
+    };
+
+    let config = match read_config().await {
+        Ok(config) => config,
+        Err(err) => {
+            eprintln!("Failed to read data: {err}");
+            return;
+        }
+    };
+    let errors = validate_document(
+        &document,
+        &config,
+        mode,
+        is_reserved_level,
+        is_reserved_kind,
+    );
+    if !errors.is_empty() {
+        println!("Nothing was imported:\n{}", errors.join("\n"));
+        return;
+    }
+
+    let existing_memories = match read_memories().await {
+        Ok(memories) => memories,
+        Err(err) => {
+            eprintln!("Failed to read memories: {err}");
+            return;
+        }
+    };
+    let existing_plans = match read_plans().await {
+        Ok(plans) => plans,
+        Err(err) => {
+            eprintln!("Failed to read plans: {err}");
+            return;
+        }
+    };
+    let memories = memories_to_import(&document, &existing_memories, mode);
+    let plans = plans_to_import(&document, &existing_plans, mode);
+    if let Err(err) = write_export_document(&document, &memories, &plans, mode).await {
+        eprintln!("Failed to write data: {err}");
+        return;
+    }
+
+    if let Err(err) = rebuild_chances_from_memories().await {
+        eprintln!("Failed to rebuild chances: {err}");
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{
+        memories_to_import, plans_to_import, validate_document, ExportDocument, ExportedConstraint,
+        ExportedFriend, ExportedMemory, ExportedPlan, ImportMode, EXPORT_VERSION,
+    };
+    use crate::config::plan::PlanInfo;
+    use crate::config::{friend_info, AkcConfig, KindInfo, LevelInfo, MemoryInfo};
+    use std::collections::BTreeMap;
+
+    fn document() -> ExportDocument {
+        ExportDocument {
+            version: EXPORT_VERSION,
+            levels: vec![LevelInfo {
+                name: "aji".to_owned(),
+                chance: 50.0,
+            }],
+            kinds: vec![KindInfo {
+                name: "call".to_owned(),
+                reduction: 0.5,
+            }],
+            friends: vec![ExportedFriend {
+                name: "Negar".to_owned(),
+                level: "aji".to_owned(),
+                note: None,
+                tags: Vec::new(),
+            }],
+            memories: vec![ExportedMemory {
+                kind: "call".to_owned(),
+                participants: vec!["Negar".to_owned(), "Arash".to_owned()],
+                created_at: "2026-10-11 18:00:00".to_owned(),
+                note: None,
+                place: None,
+                tags: Vec::new(),
+            }],
+            plans: vec![ExportedPlan {
+                kind: "call".to_owned(),
+                participants: vec!["Negar".to_owned()],
+                planned_on: "2026-10-25".to_owned(),
+                note: None,
+            }],
+            constraints: vec![ExportedConstraint {
+                target: "level".to_owned(),
+                name: "aji".to_owned(),
+                cooldown_days: Some(7),
+                interval_days: None,
+            }],
+            settings: BTreeMap::new(),
+        }
+    }
+
+    #[test]
+    fn test_validate_document() {
+        let existing = AkcConfig {
+            friends: vec![friend_info("Arash", "ki", 5.0)],
+            ..Default::default()
+        };
+        let is_reserved = |name: &str| name == "add";
+        let validate = |document: &ExportDocument, mode| {
+            validate_document(document, &existing, mode, is_reserved, is_reserved)
+        };
+
+        assert!(validate(&document(), ImportMode::Merge).is_empty());
+        assert_eq!(
+            validate(&document(), ImportMode::Replace),
+            vec!["Memory #1: friend \"Arash\" not found".to_owned()]
+        );
+
+        let mut invalid_document = document();
+        invalid_document.version = EXPORT_VERSION + 1;
+        assert_eq!(validate(&invalid_document, ImportMode::Merge).len(), 1);
+
+        let mut invalid_document = document();
+        invalid_document.levels.push(LevelInfo {
+            name: "add".to_owned(),
+            chance: 1.0,
+        });
+        invalid_document.kinds.push(KindInfo {
+            name: "add".to_owned(),
+            reduction: 1.0,
+        });
+        invalid_document.friends[0].level = "ki".to_owned();
+        invalid_document.memories[0].created_at = "someday".to_owned();
+        invalid_document.plans[0].participants.clear();
+        invalid_document.plans[0].planned_on = "2026-10-25 18:00".to_owned();
+        invalid_document.constraints[0].target = "friend".to_owned();
+        invalid_document.constraints[0].cooldown_days = Some(0);
+        invalid_document.settings = BTreeMap::from([
+            ("strategy".to_owned(), "random".to_owned()),
+            ("skip-penalty".to_owned(), "-1".to_owned()),
+            ("min-chance".to_owned(), "0.5".to_owned()),
+        ]);
+        assert_eq!(
+            validate(&invalid_document, ImportMode::Merge),
+            vec![
+                "Level name \"add\" is reserved".to_owned(),
+                "Memory kind name \"add\" is reserved".to_owned(),
+                "Level \"ki\" of friend \"Negar\" not found".to_owned(),
+                "Memory #1: date \"someday\" must be formatted as YYYY-MM-DD HH:MM:SS".to_owned(),
+                "Plan #1: no participants".to_owned(),
+                "Plan #1: date \"2026-10-25 18:00\" must be formatted as YYYY-MM-DD or YYYY-MM-DD HH:MM:SS".to_owned(),
+                "Constraint: friend \"aji\" not found".to_owned(),
+                "Constraint of friend \"aji\": days must be positive".to_owned(),
+                "Setting \"skip-penalty\": Skip penalty must be a non-negative number".to_owned(),
+                "Setting \"strategy\": Strategy must be one of \"weighted-random\", \"longest-since-contact\", \"round-robin\", \"softmax\"".to_owned(),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_memories_to_import_round_trip() {
+        let memory = |name: &str| ExportedMemory {
+            kind: "hangout".to_owned(),
+            participants: vec![name.to_owned()],
+            created_at: "2026-10-11 00:00:00".to_owned(),
+            note: None,
+            place: None,
+            tags: Vec::new(),
+        };
+        let mut exported = document();
+        exported.memories = vec![memory("Negar"), memory("Sara")];
+        let json = serde_json::to_string(&exported).unwrap();
+        let imported = serde_json::from_str::<ExportDocument>(&json).unwrap();
+        let existing = exported
+            .memories
+            .iter()
+            .enumerate()
+            .map(|(index, memory)| MemoryInfo {
+                id: index as i64 + 1,
+                kind: memory.kind.clone(),
+                names: memory.participants.clone(),
+                created_at: memory.created_at.clone(),
+                note: None,
+                place: None,
+                tags: Vec::new(),
+            })
+            .collect::<Vec<MemoryInfo>>();
+        let participants = |memories: Vec<&ExportedMemory>| {
+            memories
+                .iter()
+                .map(|memory| memory.participants.join(", "))
+                .collect::<Vec<String>>()
+        };
+
+        assert_eq!(
+            participants(memories_to_import(
+                &imported,
+                &existing,
+                ImportMode::Replace
+            )),
+            vec!["Negar", "Sara"]
+        );
+        assert_eq!(
+            participants(memories_to_import(&imported, &[], ImportMode::Merge)),
+            vec!["Negar", "Sara"]
+        );
+        assert!(memories_to_import(&imported, &existing, ImportMode::Merge).is_empty());
+        assert_eq!(
+            participants(memories_to_import(
+                &imported,
+                &existing[..1],
+                ImportMode::Merge
+            )),
+            vec!["Sara"]
+        );
+    }
+
+    #[test]
+    fn test_plans_to_import() {
+        let imported = document();
+        let mut existing = vec![PlanInfo {
+            id: 1,
+            kind: "call".to_owned(),
+            participants: vec!["Negar".to_owned()],
+            planned_on: "2026-10-25".to_owned(),
+            note: Some("Catch up".to_owned()),
+            created_at: "2026-10-18 09:00:00".to_owned(),
+        }];
+
+        assert_eq!(plans_to_import(&imported, &[], ImportMode::Merge).len(), 1);
+        assert!(plans_to_import(&imported, &existing, ImportMode::Merge).is_empty());
+        assert_eq!(
+            plans_to_import(&imported, &existing, ImportMode::Replace).len(),
+            1
+        );
+        existing[0].planned_on = "2026-10-26".to_owned();
+        assert_eq!(
+            plans_to_import(&imported, &existing, ImportMode::Merge).len(),
+            1
+        );
+    }
+}
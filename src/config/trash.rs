@@ -0,0 +1,198 @@
+use serde::Serialize;
+use sqlx::Row;
+use std::collections::HashMap;
+
+use super::{
+    group_memory_rows, memory_row, open_pool,
+    output::{self, OutputFormat},
+    read_config, rebuild_chances_from_memories, utils, MemoryInfo,
+};
+
+/// An undone memory which can be redone.
+#[derive(Serialize)]
+pub struct TrashedMemory {
+    #[serde(flatten)]
+    pub memory: MemoryInfo,
+    pub trashed_at: String,
+}
+
+/// Moves a memory with its participants and tags into the trash.
+pub(super) async fn trash_memory(id: i64) -> Result<bool, sqlx::Error> {
+    let pool = open_pool().await?;
+    let mut transaction = pool.begin().await?;
+    let result = sqlx::query(
+        "INSERT INTO memory_trash (memory_id, kind, created_at, note, place)
+            SELECT id, kind, created_at, note, place FROM memories WHERE id = ?1",
+    )
+    .bind(id)
+    .execute(&mut *transaction)
+    .await?;
+    if result.rows_affected() == 0 {
+        return Ok(false);
+    }
+
+    sqlx::query(
+        "INSERT INTO memory_trash_participants (memory_id, friend_id)
+            SELECT memory_id, friend_id FROM memory_participants WHERE memory_id = ?1",
+    )
+    .bind(id)
+    .execute(&mut *transaction)
+    .await?;
+    sqlx::query(
+        "INSERT INTO memory_trash_tags (memory_id, tag)
+            SELECT memory_id, tag FROM memory_tags WHERE memory_id = ?1",
+    )
+    .bind(id)
+    .execute(&mut *transaction)
+    .await?;
+    sqlx::query("DELETE FROM memories WHERE id = ?1")
+        .bind(id)
+        .execute(&mut *transaction)
+        .await?;
+    transaction.commit().await?;
+    Ok(true)
+}
+
+/// Reads the id and kind of the most recently trashed memory.
+async fn read_last_trashed_memory() -> Result<Option<(i64, String)>, sqlx::Error> {
+    let pool = open_pool().await?;
+    let row =
+        sqlx::query("SELECT memory_id, kind FROM memory_trash ORDER BY position DESC LIMIT 1")
+            .fetch_optional(&pool)
+            .await?;
+    Ok(row.map(|row| (row.get("memory_id"), row.get("kind"))))
+}
+
+/// Moves a trashed memory back, keeping its original id and timestamp.
+async fn restore_memory(id: i64) -> Result<(), sqlx::Error> {
+    let pool = open_pool().await?;
+    let mut transaction = pool.begin().await?;
+    sqlx::query(
+        "INSERT INTO memories (id, kind, created_at, note, place)
+            SELECT memory_id, kind, created_at, note, place FROM memory_trash
+            WHERE memory_id = ?1",
+    )
+    .bind(id)
+    .execute(&mut *transaction)
+    .await?;
+    sqlx::query(
+        "INSERT INTO memory_participants (memory_id, friend_id)
+            SELECT memory_id, friend_id FROM memory_trash_participants WHERE memory_id = ?1",
+    )
+    .bind(id)
+    .execute(&mut *transaction)
+    .await?;
+    sqlx::query(
+        "INSERT INTO memory_tags (memory_id, tag)
+            SELECT memory_id, tag FROM memory_trash_tags WHERE memory_id = ?1",
+    )
+    .bind(id)
+    .execute(&mut *transaction)
+    .await?;
+    sqlx::query("DELETE FROM memory_trash WHERE memory_id = ?1")
+        .bind(id)
+        .execute(&mut *transaction)
+        .await?;
+    transaction.commit().await
+}
+
+async fn read_trashed_memories() -> Result<Vec<TrashedMemory>, sqlx::Error> {
+    let pool = open_pool().await?;
+    // Same columns as `MEMORY_COLUMNS`, so rows can be read by `memory_row`.
+    let rows = sqlx::query(
+        "SELECT memory_trash.memory_id AS id, memory_trash.kind, memory_trash.created_at,
+            memory_trash.note, memory_trash.place,
+            (SELECT group_concat(tag, char(10)) FROM memory_trash_tags
+                WHERE memory_trash_tags.memory_id = memory_trash.memory_id) AS tags,
+            friends.name, memory_trash.trashed_at
+            FROM memory_trash
+            LEFT JOIN memory_trash_participants
+                ON memory_trash_participants.memory_id = memory_trash.memory_id
+            LEFT JOIN friends ON friends.id = memory_trash_participants.friend_id
+            ORDER BY memory_trash.position DESC, friends.name ASC",
+    )
+    .fetch_all(&pool)
+    .await?;
+
+    let mut trashed_at_by_id: HashMap<i64, String> = HashMap::new();
+    let rows = rows
+        .into_iter()
+        .map(|row| {
+            trashed_at_by_id.insert(row.get("id"), row.get("trashed_at"));
+            memory_row(row)
+        })
+        .collect();
+    let trashed_memories = group_memory_rows(rows)
+        .into_iter()
+        .map(|memory| TrashedMemory {
+            trashed_at: trashed_at_by_id.remove(&memory.id).unwrap_or_default(),
+            memory,
+        })
+        .collect();
+    Ok(trashed_memories)
+}
+
+async fn delete_trashed_memories() -> Result<bool, sqlx::Error> {
+    let pool = open_pool().await?;
+    let result = sqlx::query("DELETE FROM memory_trash")
+        .execute(&pool)
+        .await?;
+    Ok(result.rows_affected() > 0)
+}
+
+/// Restores the most recently undone memory.
+pub async fn redo_memory() {
+    let (id, kind) = match read_last_trashed_memory().await {
+        Ok(Some(last_trashed_memory)) => last_trashed_memory,
+        Ok(None) => {
+            println!("No memory to redo");
+            return;
+        }
+        Err(err) => {
+            eprintln!("Failed to read memories: {err}");
+            return;
+        }
+    };
+    let config = match read_config().await {
+        Ok(config) => config,
+        Err(err) => {
+            eprintln!("Failed to read data: {err}");
+            return;
+        }
+    };
+    if utils::kind_reduction(&config, &kind).is_none() {
+        println!("Memory kind \"{kind}\" not found, please add it before redoing");
+        return;
+    }
+
+    if let Err(err) = restore_memory(id).await {
+        eprintln!("Failed to restore memory: {err}");
+        return;
+    }
+
+    if let Err(err) = rebuild_chances_from_memories().await {
+        eprintln!("Failed to rebuild chances: {err}");
+    }
+}
+
+/// Lists undone memories, most recently undone first.
+pub async fn list_trash(format: OutputFormat) {
+    let trashed_memories = match read_trashed_memories().await {
+        Ok(trashed_memories) => trashed_memories,
+        Err(err) => {
+            eprintln!("Failed to read memories: {err}");
+            return;
+        }
+    };
+
+    println!("{}", output::render(format, &trashed_memories));
+}
+
+/// Permanently deletes all undone memories.
+pub async fn purge_trash() {
+    match delete_trashed_memories().await {
+        Ok(true) => {}
+        Ok(false) => println!("Trash is already empty"),
+        Err(err) => eprintln!("Failed to delete memories: {err}"),
+    }
+}
@@ -1,4 +1,18 @@
-use super::{default_chance, default_reduction, AkcConfig, FriendInfo};
+use rand::{
+    distr::{weighted::WeightedIndex, Distribution},
+    Rng,
+};
+use std::collections::{HashMap, HashSet};
+
+use super::{
+    constraint::{ConstraintRecord, Constraints},
+    status::FriendStatus,
+    strategy::SuggestionStrategy,
+    AkcConfig, FriendInfo, KindInfo, LevelInfo, SuggestFilter,
+};
+
+#[cfg(test)]
+use super::{default_chance, default_reduction, Settings};
 
 pub fn is_name_duplicate(config: &AkcConfig, name: &str) -> bool {
     config
@@ -7,14 +21,144 @@ pub fn is_name_duplicate(config: &AkcConfig, name: &str) -> bool {
         .any(|friend_info| friend_info.name == name)
 }
 
+pub fn level_chance(config: &AkcConfig, level: &str) -> Option<f64> {
+    config
+        .levels
+        .iter()
+        .find(|level_info| level_info.name == level)
+        .map(|level_info| level_info.chance)
+}
+
+pub fn kind_reduction(config: &AkcConfig, kind: &str) -> Option<f64> {
+    config
+        .kinds
+        .iter()
+        .find(|kind_info| kind_info.name == kind)
+        .map(|kind_info| kind_info.reduction)
+}
+
 pub fn filter_config_by_enough_chance(config: &AkcConfig) -> Vec<&FriendInfo> {
     config
         .friends
         .iter()
-        .filter(|friend_info| friend_info.chance >= default_reduction::TEXT)
+        .filter(|friend_info| friend_info.chance >= config.settings.min_chance)
         .collect()
 }
 
+/// Keeps only the friends matching `filter`. `tagged_names` are the friends with the
+/// filter's tag, if it has one.
+pub fn filter_suggestion_candidates(
+    config: &mut AkcConfig,
+    filter: &SuggestFilter,
+    tagged_names: Option<&HashSet<String>>,
+) {
+    let reduction = filter
+        .for_kind
+        .as_deref()
+        .and_then(|kind| kind_reduction(config, kind));
+    config.friends.retain(|friend_info| {
+        filter
+            .level
+            .as_ref()
+            .is_none_or(|level| friend_info.level == *level)
+            && tagged_names.is_none_or(|names| names.contains(&friend_info.name))
+            && !filter.exclude.contains(&friend_info.name)
+            && reduction.is_none_or(|reduction| friend_info.chance >= reduction)
+    })
+}
+
+/// Drops friends in cooldown and splits the others into those overdue for their interval
+/// and the rest, in that order. Friends without any memory count as overdue but never in
+/// cooldown.
+pub fn apply_constraints<'a>(
+    candidates: Vec<&'a FriendInfo>,
+    constraints: &Constraints,
+    days_since_last_memory: &HashMap<String, f64>,
+) -> [Vec<&'a FriendInfo>; 2] {
+    let (overdue, rest) = candidates
+        .into_iter()
+        .filter(|friend_info| {
+            let constraint = constraints.for_friend(&friend_info.name, &friend_info.level);
+            match (
+                constraint.cooldown_days,
+                days_since_last_memory.get(&friend_info.name),
+            ) {
+                (Some(cooldown_days), Some(&days)) => days >= cooldown_days as f64,
+                _ => true,
+            }
+        })
+        .partition(|friend_info| {
+            let constraint = constraints.for_friend(&friend_info.name, &friend_info.level);
+            match (
+                constraint.interval_days,
+                days_since_last_memory.get(&friend_info.name),
+            ) {
+                (Some(interval_days), Some(&days)) => days >= interval_days as f64,
+                (Some(_), None) => true,
+                (None, _) => false,
+            }
+        });
+    [overdue, rest]
+}
+
+/// Draws up to `count` distinct candidates, each with a probability proportional to their
+/// weight from `strategy` among those not drawn yet. Every draw is made from the first of `tiers` with a candidate of positive
+/// weight left, so earlier tiers are exhausted before later ones are drawn from. With
+/// `companions`, each friend must have shared a memory with everyone drawn before them.
+pub fn sample_without_replacement<'a, R: Rng + ?Sized>(
+    tiers: &[Vec<&'a FriendInfo>],
+    count: usize,
+    companions: Option<&HashMap<String, HashSet<String>>>,
+    strategy: &mut dyn SuggestionStrategy,
+    rng: &mut R,
+) -> Vec<&'a FriendInfo> {
+    let mut remaining_tiers = tiers.to_vec();
+    if let Some(companions) = companions {
+        // A group can only start with someone who has shared a memory with another candidate.
+        for tier in &mut remaining_tiers {
+            tier.retain(|friend| {
+                companions.get(&friend.name).is_some_and(|names| {
+                    tiers
+                        .iter()
+                        .flatten()
+                        .any(|candidate| names.contains(&candidate.name))
+                })
+            });
+        }
+    }
+    let mut picks = Vec::new();
+    while picks.len() < count {
+        // Stops once no candidate with a positive weight is left.
+        let Some((tier, weighted_dist)) = remaining_tiers.iter_mut().find_map(|tier| {
+            let weighted_dist = WeightedIndex::new(strategy.weights(tier)).ok()?;
+            Some((tier, weighted_dist))
+        }) else {
+            break;
+        };
+        let picked_friend = tier.remove(weighted_dist.sample(rng));
+        picks.push(picked_friend);
+        strategy.record_pick(picked_friend);
+
+        if let Some(companions) = companions {
+            let picked_companions = companions.get(&picked_friend.name);
+            for tier in &mut remaining_tiers {
+                tier.retain(|friend| {
+                    picked_companions.is_some_and(|names| names.contains(&friend.name))
+                });
+            }
+        }
+    }
+    picks
+}
+
+/// Identifies a memory by its kind, time and participants, in any order.
+pub fn memory_key(kind: &str, created_at: &str, names: &[String]) -> (String, String, Vec<String>) {
+    let mut names = names.to_vec();
+    names.sort();
+    names.dedup();
+    (kind.to_owned(), created_at.to_owned(), names)
+}
+
 pub fn get_unknown_names<'a>(config: &AkcConfig, names: &'a [String]) -> Vec<&'a String> {
     let all_names = config
         .friends
@@ -27,6 +171,43 @@ pub fn get_unknown_names<'a>(config: &AkcConfig, names: &'a [String]) -> Vec<&'a
         .collect::<Vec<&String>>()
 }
 
+pub fn unique_names(names: &[String]) -> Vec<String> {
+    let mut unique_names: Vec<String> = Vec::new();
+    for name in names {
+        if !unique_names.contains(name) {
+            unique_names.push(name.clone());
+        }
+    }
+    unique_names
+}
+
+/// Checks that `value` is a calendar date formatted as `YYYY-MM-DD`.
+pub fn is_valid_date(value: &str) -> bool {
+    let parts = value.split('-').collect::<Vec<&str>>();
+    let [year, month, day] = parts[..] else {
+        return false;
+    };
+    if year.len() != 4 || month.len() != 2 || day.len() != 2 {
+        return false;
+    }
+    let (Ok(year), Ok(month), Ok(day)) = (
+        year.parse::<u32>(),
+        month.parse::<u32>(),
+        day.parse::<u32>(),
+    ) else {
+        return false;
+    };
+    let is_leap_year = year % 4 == 0 && (year % 100 != 0 || year % 400 == 0);
+    let days_in_month = match month {
+        1 | 3 | 5 | 7 | 8 | 10 | 12 => 31,
+        4 | 6 | 9 | 11 => 30,
+        2 if is_leap_year => 29,
+        2 => 28,
+        _ => return false,
+    };
+    (1..=days_in_month).contains(&day)
+}
+
 pub fn get_config_total_chance(config: &AkcConfig, excluded_names: &[String]) -> f64 {
     config
         .friends
@@ -41,17 +222,16 @@ pub fn increase_chances_by_unit(
     unit_added_chance: f64,
     excluded_names: &[String],
 ) {
+    let levels = &config.levels;
     config
         .friends
         .iter_mut()
         .filter(|friend_info| !excluded_names.contains(&friend_info.name))
         .for_each(|friend_info| {
-            let level_chance = match friend_info.level.as_str() {
-                "aji" => default_chance::AJI,
-                "ki" => default_chance::KI,
-                "chi" => default_chance::CHI,
-                _ => 0.0,
-            };
+            let level_chance = levels
+                .iter()
+                .find(|level_info| level_info.name == friend_info.level)
+                .map_or(0.0, |level_info| level_info.chance);
             friend_info.chance += level_chance * unit_added_chance;
         })
 }
@@ -66,18 +246,44 @@ pub fn decrease_chances_by_reduction(config: &mut AkcConfig, reduction: f64, nam
         })
 }
 
-pub fn list_friends(config: &AkcConfig) -> String {
-    let mut friends_vector = config
-        .friends
-        .iter()
-        .map(|friend_info| friend_info.name.to_owned())
-        .collect::<Vec<String>>();
-    friends_vector.sort();
+/// Moves chances towards their level base chance, halving the distance every
+/// `recovery_half_life` days since the latest memory with the friend.
+pub fn recover_chances(config: &mut AkcConfig, days_since_last_memory: &HashMap<String, f64>) {
+    let levels = &config.levels;
+    let half_life = config.settings.recovery_half_life;
+    config.friends.iter_mut().for_each(|friend_info| {
+        let Some(&days) = days_since_last_memory.get(&friend_info.name) else {
+            return;
+        };
+        let Some(base_chance) = levels
+            .iter()
+            .find(|level_info| level_info.name == friend_info.level)
+            .map(|level_info| level_info.chance)
+        else {
+            return;
+        };
+        let remaining = 0.5_f64.powf(days.max(0.0) / half_life);
+        friend_info.chance = base_chance + (friend_info.chance - base_chance) * remaining;
+    })
+}
 
-    friends_vector.join("\n")
+/// Lowers chances by the skip penalty for each suggestion skipped since the latest memory.
+pub fn apply_skip_penalties(config: &mut AkcConfig, skip_counts: &HashMap<String, i64>) {
+    let skip_penalty = config.settings.skip_penalty;
+    config.friends.iter_mut().for_each(|friend_info| {
+        if let Some(&skips) = skip_counts.get(&friend_info.name) {
+            friend_info.chance -= skip_penalty * skips as f64;
+        }
+    })
 }
 
-pub fn list_friends_by_chance(config: &AkcConfig) -> String {
+pub fn sort_friends_by_name(config: &AkcConfig) -> Vec<&FriendInfo> {
+    let mut friends_vector = config.friends.iter().collect::<Vec<&FriendInfo>>();
+    friends_vector.sort_by(|left, right| left.name.cmp(&right.name));
+    friends_vector
+}
+
+pub fn sort_friends_by_chance(config: &AkcConfig) -> Vec<&FriendInfo> {
     let mut friends_vector = config.friends.iter().collect::<Vec<&FriendInfo>>();
     friends_vector.sort_by(|left, right| {
         right
@@ -85,45 +291,269 @@ pub fn list_friends_by_chance(config: &AkcConfig) -> String {
             .total_cmp(&left.chance)
             .then_with(|| left.name.cmp(&right.name))
     });
-
     friends_vector
+}
+
+pub fn list_friends(config: &AkcConfig) -> String {
+    sort_friends_by_name(config)
+        .iter()
+        .map(|friend_info| friend_info.name.to_owned())
+        .collect::<Vec<String>>()
+        .join("\n")
+}
+
+pub fn list_friends_by_chance(config: &AkcConfig) -> String {
+    sort_friends_by_chance(config)
         .iter()
         .map(|friend| format!("{} ({:.2})", friend.name, friend.chance))
         .collect::<Vec<String>>()
         .join("\n")
 }
 
-pub fn search_friends(config: &AkcConfig, query: &str) -> String {
+pub fn sort_levels(config: &AkcConfig) -> Vec<&LevelInfo> {
+    let mut levels_vector = config.levels.iter().collect::<Vec<&LevelInfo>>();
+    levels_vector.sort_by(|left, right| {
+        right
+            .chance
+            .total_cmp(&left.chance)
+            .then_with(|| left.name.cmp(&right.name))
+    });
+    levels_vector
+}
+
+pub fn list_levels(config: &AkcConfig) -> String {
+    sort_levels(config)
+        .iter()
+        .map(|level| format!("{} ({:.2})", level.name, level.chance))
+        .collect::<Vec<String>>()
+        .join("\n")
+}
+
+pub fn sort_kinds(config: &AkcConfig) -> Vec<&KindInfo> {
+    let mut kinds_vector = config.kinds.iter().collect::<Vec<&KindInfo>>();
+    kinds_vector.sort_by(|left, right| {
+        right
+            .reduction
+            .total_cmp(&left.reduction)
+            .then_with(|| left.name.cmp(&right.name))
+    });
+    kinds_vector
+}
+
+pub fn list_kinds(config: &AkcConfig) -> String {
+    sort_kinds(config)
+        .iter()
+        .map(|kind| format!("{} ({:.2})", kind.name, kind.reduction))
+        .collect::<Vec<String>>()
+        .join("\n")
+}
+
+pub fn list_defaults(config: &AkcConfig) -> String {
+    let mut lines = Vec::new();
+    let mut levels_vector = config.levels.iter().collect::<Vec<&LevelInfo>>();
+    levels_vector.sort_by(|left, right| left.name.cmp(&right.name));
+    for level in levels_vector {
+        lines.push(format!("chance.{} = {:.2}", level.name, level.chance));
+    }
+    let mut kinds_vector = config.kinds.iter().collect::<Vec<&KindInfo>>();
+    kinds_vector.sort_by(|left, right| left.name.cmp(&right.name));
+    for kind in kinds_vector {
+        lines.push(format!("reduction.{} = {:.2}", kind.name, kind.reduction));
+    }
+    lines.push(format!("min-chance = {:.2}", config.settings.min_chance));
+    lines.push(format!("chance-model = {}", config.settings.chance_model));
+    lines.push(format!(
+        "recovery-half-life = {:.2}",
+        config.settings.recovery_half_life
+    ));
+    lines.push(format!(
+        "skip-penalty = {:.2}",
+        config.settings.skip_penalty
+    ));
+    lines.push(format!("strategy = {}", config.settings.strategy));
+    lines.push(format!(
+        "softmax-temperature = {:.2}",
+        config.settings.softmax_temperature
+    ));
+
+    lines.join("\n")
+}
+
+pub fn constraint_records(constraints: &Constraints) -> Vec<ConstraintRecord> {
+    let mut friend_records = constraints
+        .friends
+        .iter()
+        .map(|(name, constraint)| ConstraintRecord::new("friend", name, constraint))
+        .collect::<Vec<ConstraintRecord>>();
+    friend_records.sort_by(|left, right| left.name.cmp(&right.name));
+    let mut level_records = constraints
+        .levels
+        .iter()
+        .map(|(level, constraint)| ConstraintRecord::new("level", level, constraint))
+        .collect::<Vec<ConstraintRecord>>();
+    level_records.sort_by(|left, right| left.name.cmp(&right.name));
+
+    friend_records.extend(level_records);
+    friend_records
+}
+
+pub fn list_constraints(records: &[ConstraintRecord]) -> String {
+    records
+        .iter()
+        .map(|record| {
+            let mut rules = Vec::new();
+            if let Some(cooldown_days) = record.cooldown_days {
+                rules.push(format!("cooldown {cooldown_days} days"));
+            }
+            if let Some(interval_days) = record.interval_days {
+                rules.push(format!("at least every {interval_days} days"));
+            }
+            format!("{} {}: {}", record.target, record.name, rules.join(", "))
+        })
+        .collect::<Vec<String>>()
+        .join("\n")
+}
+
+/// Renders rows as left-aligned columns separated by two spaces, under a header row.
+pub fn render_table(headers: &[&str], rows: &[Vec<String>]) -> String {
+    let mut widths = headers
+        .iter()
+        .map(|header| header.chars().count())
+        .collect::<Vec<usize>>();
+    for row in rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+
+    let render_row = |cells: Vec<&str>| {
+        cells
+            .iter()
+            .zip(&widths)
+            .map(|(cell, width)| format!("{cell:width$}"))
+            .collect::<Vec<String>>()
+            .join("  ")
+            .trim_end()
+            .to_owned()
+    };
+    let mut lines = vec![render_row(headers.to_vec())];
+    lines.extend(
+        rows.iter()
+            .map(|row| render_row(row.iter().map(String::as_str).collect())),
+    );
+    lines.join("\n")
+}
+
+pub fn render_status(mut statuses: Vec<FriendStatus>) -> String {
+    statuses.sort_by(|left, right| {
+        right
+            .probability
+            .total_cmp(&left.probability)
+            .then_with(|| right.chance.total_cmp(&left.chance))
+            .then_with(|| left.name.cmp(&right.name))
+    });
+
+    let rows = statuses
+        .iter()
+        .map(|status| {
+            let last_date = status
+                .last_memory_date
+                .clone()
+                .unwrap_or_else(|| "-".to_owned());
+            let last_kind = status
+                .last_memory_kind
+                .clone()
+                .unwrap_or_else(|| "-".to_owned());
+            let memory_counts = if status.memory_counts.is_empty() {
+                "-".to_owned()
+            } else {
+                status
+                    .memory_counts
+                    .iter()
+                    .map(|(kind, count)| format!("{kind}: {count}"))
+                    .collect::<Vec<String>>()
+                    .join(", ")
+            };
+            vec![
+                status.name.clone(),
+                status.level.clone(),
+                format!("{:.2}", status.chance),
+                format!("{:.1}%", status.probability),
+                last_date,
+                last_kind,
+                memory_counts,
+            ]
+        })
+        .collect::<Vec<Vec<String>>>();
+
+    render_table(
+        &[
+            "NAME",
+            "LEVEL",
+            "CHANCE",
+            "PROBABILITY",
+            "LAST MEMORY",
+            "KIND",
+            "MEMORIES",
+        ],
+        &rows,
+    )
+}
+
+pub fn find_friends<'a>(config: &'a AkcConfig, query: &str) -> Vec<&'a FriendInfo> {
     let query = query.to_lowercase();
     let mut matches = config
         .friends
         .iter()
         .filter(|friend| friend.name.to_lowercase().contains(&query))
+        .collect::<Vec<&FriendInfo>>();
+    matches.sort_by(|left, right| left.name.cmp(&right.name));
+    matches
+}
+
+pub fn search_friends(config: &AkcConfig, query: &str) -> String {
+    find_friends(config, query)
+        .iter()
         .map(|friend| friend.name.clone())
-        .collect::<Vec<String>>();
-    matches.sort();
-    matches.join("\n")
+        .collect::<Vec<String>>()
+        .join("\n")
 }
 
 #[cfg(test)]
 mod test {
+    use super::super::constraint::ConnectionConstraint;
+    use super::super::friend_info;
+    use super::super::strategy::WeightedRandom;
     use super::*;
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+    use std::collections::BTreeMap;
+
+    fn default_levels() -> Vec<LevelInfo> {
+        vec![
+            LevelInfo {
+                name: "aji".to_owned(),
+                chance: default_chance::AJI,
+            },
+            LevelInfo {
+                name: "ki".to_owned(),
+                chance: default_chance::KI,
+            },
+            LevelInfo {
+                name: "chi".to_owned(),
+                chance: default_chance::CHI,
+            },
+        ]
+    }
 
     #[test]
     fn test_is_name_duplicate() {
         let config = AkcConfig {
             friends: vec![
-                FriendInfo {
-                    name: "John".to_owned(),
-                    chance: default_chance::AJI,
-                    level: "aji".to_owned(),
-                },
-                FriendInfo {
-                    name: "Doe".to_owned(),
-                    chance: default_chance::KI,
-                    level: "ki".to_owned(),
-                },
+                friend_info("John", "aji", default_chance::AJI),
+                friend_info("Doe", "ki", default_chance::KI),
             ],
+            ..Default::default()
         };
 
         assert!(is_name_duplicate(&config, "John"));
@@ -132,46 +562,197 @@ mod test {
     }
 
     #[test]
-    fn test_filter_config_by_enough_chance() {
+    fn test_level_chance() {
         let config = AkcConfig {
-            friends: vec![
-                FriendInfo {
-                    name: "John".to_owned(),
-                    chance: default_chance::AJI,
-                    level: "aji".to_owned(),
-                },
-                FriendInfo {
-                    name: "Doe".to_owned(),
-                    chance: default_reduction::TEXT,
-                    level: "ki".to_owned(),
+            levels: default_levels(),
+            ..Default::default()
+        };
+
+        assert_eq!(level_chance(&config, "ki"), Some(default_chance::KI));
+        assert_eq!(level_chance(&config, "family"), None);
+    }
+
+    #[test]
+    fn test_kind_reduction() {
+        let config = AkcConfig {
+            kinds: vec![
+                KindInfo {
+                    name: "hangout".to_owned(),
+                    reduction: default_reduction::HANGOUT,
                 },
-                FriendInfo {
-                    name: "Jane".to_owned(),
-                    chance: 0.0,
-                    level: "chi".to_owned(),
+                KindInfo {
+                    name: "dinner".to_owned(),
+                    reduction: 1.5,
                 },
             ],
+            ..Default::default()
+        };
+
+        assert_eq!(kind_reduction(&config, "dinner"), Some(1.5));
+        assert_eq!(kind_reduction(&config, "email"), None);
+    }
+
+    #[test]
+    fn test_filter_config_by_enough_chance() {
+        let config = AkcConfig {
+            friends: vec![
+                friend_info("John", "aji", default_chance::AJI),
+                friend_info("Doe", "ki", default_reduction::TEXT),
+                friend_info("Jane", "chi", 0.0),
+            ],
+            ..Default::default()
         };
 
         let filtered_config = filter_config_by_enough_chance(&config);
         assert_eq!(filtered_config.len(), 2);
     }
 
+    #[test]
+    fn test_filter_config_by_enough_chance_with_custom_minimum() {
+        let config = AkcConfig {
+            friends: vec![
+                friend_info("John", "aji", default_chance::AJI),
+                friend_info("Doe", "ki", default_reduction::HANGOUT),
+            ],
+            settings: Settings {
+                min_chance: 10.0,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+
+        let filtered_config = filter_config_by_enough_chance(&config);
+        assert_eq!(filtered_config.len(), 1);
+        assert_eq!(filtered_config[0].name, "John");
+    }
+
+    #[test]
+    fn test_filter_suggestion_candidates() {
+        let config = || AkcConfig {
+            friends: vec![
+                friend_info("John", "ki", 3.0),
+                friend_info("Doe", "ki", 1.0),
+                friend_info("Jane", "aji", 50.0),
+            ],
+            kinds: vec![KindInfo {
+                name: "hangout".to_owned(),
+                reduction: 2.0,
+            }],
+            ..Default::default()
+        };
+        let names = |config: &AkcConfig| {
+            config
+                .friends
+                .iter()
+                .map(|friend_info| friend_info.name.clone())
+                .collect::<Vec<String>>()
+        };
+
+        let mut filtered_config = config();
+        filter_suggestion_candidates(&mut filtered_config, &SuggestFilter::default(), None);
+        assert_eq!(names(&filtered_config), vec!["John", "Doe", "Jane"]);
+
+        let mut filtered_config = config();
+        let filter = SuggestFilter {
+            level: Some("ki".to_owned()),
+            for_kind: Some("hangout".to_owned()),
+            ..Default::default()
+        };
+        filter_suggestion_candidates(&mut filtered_config, &filter, None);
+        assert_eq!(names(&filtered_config), vec!["John"]);
+
+        let mut filtered_config = config();
+        let filter = SuggestFilter {
+            tag: Some("work".to_owned()),
+            exclude: vec!["Jane".to_owned()],
+            ..Default::default()
+        };
+        let tagged_names = HashSet::from(["Jane".to_owned(), "Doe".to_owned()]);
+        filter_suggestion_candidates(&mut filtered_config, &filter, Some(&tagged_names));
+        assert_eq!(names(&filtered_config), vec!["Doe"]);
+    }
+
+    #[test]
+    fn test_apply_constraints() {
+        let config = AkcConfig {
+            friends: vec![
+                friend_info("John", "aji", default_chance::AJI),
+                friend_info("Doe", "ki", default_chance::KI),
+                friend_info("Jane", "chi", default_chance::CHI),
+            ],
+            ..Default::default()
+        };
+        let mut constraints = Constraints::default();
+        constraints.friends.insert(
+            "John".to_owned(),
+            ConnectionConstraint {
+                cooldown_days: Some(7),
+                interval_days: None,
+            },
+        );
+        let days_since_last_memory =
+            HashMap::from([("John".to_owned(), 3.0), ("Jane".to_owned(), 40.0)]);
+
+        let tier_names = |constraints: &Constraints| {
+            apply_constraints(
+                config.friends.iter().collect(),
+                constraints,
+                &days_since_last_memory,
+            )
+            .map(|tier| {
+                tier.iter()
+                    .map(|friend| friend.name.as_str())
+                    .collect::<Vec<&str>>()
+            })
+        };
+        assert_eq!(tier_names(&constraints), [vec![], vec!["Doe", "Jane"]]);
+
+        constraints.levels.insert(
+            "chi".to_owned(),
+            ConnectionConstraint {
+                cooldown_days: None,
+                interval_days: Some(30),
+            },
+        );
+        // Overdue friends come first, the others are still drawn from after them.
+        assert_eq!(tier_names(&constraints), [vec!["Jane"], vec!["Doe"]]);
+    }
+
+    #[test]
+    fn test_constraints_for_friend_prefers_friend_rules() {
+        let mut constraints = Constraints::default();
+        constraints.friends.insert(
+            "John".to_owned(),
+            ConnectionConstraint {
+                cooldown_days: Some(3),
+                interval_days: None,
+            },
+        );
+        constraints.levels.insert(
+            "aji".to_owned(),
+            ConnectionConstraint {
+                cooldown_days: Some(7),
+                interval_days: Some(30),
+            },
+        );
+
+        assert_eq!(
+            constraints.for_friend("John", "aji"),
+            ConnectionConstraint {
+                cooldown_days: Some(3),
+                interval_days: Some(30),
+            }
+        );
+    }
+
     #[test]
     fn test_get_unknown_names() {
         let config = AkcConfig {
             friends: vec![
-                FriendInfo {
-                    name: "John".to_owned(),
-                    chance: default_chance::AJI,
-                    level: "aji".to_owned(),
-                },
-                FriendInfo {
-                    name: "Doe".to_owned(),
-                    chance: default_chance::KI,
-                    level: "ki".to_owned(),
-                },
+                friend_info("John", "aji", default_chance::AJI),
+                friend_info("Doe", "ki", default_chance::KI),
             ],
+            ..Default::default()
         };
 
         let names = vec!["John".to_owned(), "Doe".to_owned(), "Jane".to_owned()];
@@ -181,26 +762,34 @@ mod test {
         assert_eq!(unknown_names[0], "Jane");
     }
 
+    #[test]
+    fn test_unique_names() {
+        let names = vec!["John".to_owned(), "Doe".to_owned(), "John".to_owned()];
+        assert_eq!(
+            unique_names(&names),
+            vec!["John".to_owned(), "Doe".to_owned()]
+        );
+    }
+
+    #[test]
+    fn test_is_valid_date() {
+        assert!(is_valid_date("2026-10-11"));
+        assert!(is_valid_date("2024-02-29"));
+        assert!(!is_valid_date("2026-02-29"));
+        assert!(!is_valid_date("2026-13-01"));
+        assert!(!is_valid_date("2026-10-1"));
+        assert!(!is_valid_date("yesterday"));
+    }
+
     #[test]
     fn test_get_config_total_chance() {
         let config = AkcConfig {
             friends: vec![
-                FriendInfo {
-                    name: "John".to_owned(),
-                    chance: default_chance::AJI,
-                    level: "aji".to_owned(),
-                },
-                FriendInfo {
-                    name: "Doe".to_owned(),
-                    chance: default_chance::KI,
-                    level: "ki".to_owned(),
-                },
-                FriendInfo {
-                    name: "Doe2".to_owned(),
-                    chance: default_chance::CHI,
-                    level: "chi".to_owned(),
-                },
+                friend_info("John", "aji", default_chance::AJI),
+                friend_info("Doe", "ki", default_chance::KI),
+                friend_info("Doe2", "chi", default_chance::CHI),
             ],
+            ..Default::default()
         };
 
         let total_chance = get_config_total_chance(&config, &["Doe".to_owned()]);
@@ -211,22 +800,12 @@ mod test {
     fn test_increase_chances_by_unit() {
         let mut config = AkcConfig {
             friends: vec![
-                FriendInfo {
-                    name: "John".to_owned(),
-                    chance: default_chance::AJI,
-                    level: "aji".to_owned(),
-                },
-                FriendInfo {
-                    name: "Doe".to_owned(),
-                    chance: default_chance::KI,
-                    level: "ki".to_owned(),
-                },
-                FriendInfo {
-                    name: "Doe2".to_owned(),
-                    chance: default_chance::CHI,
-                    level: "chi".to_owned(),
-                },
+                friend_info("John", "aji", default_chance::AJI),
+                friend_info("Doe", "ki", default_chance::KI),
+                friend_info("Doe2", "chi", default_chance::CHI),
             ],
+            levels: default_levels(),
+            ..Default::default()
         };
 
         increase_chances_by_unit(&mut config, 0.1, &["Doe".to_owned()]);
@@ -246,22 +825,11 @@ mod test {
     fn test_decrease_chances_by_reduction() {
         let mut config = AkcConfig {
             friends: vec![
-                FriendInfo {
-                    name: "John".to_owned(),
-                    chance: default_chance::AJI,
-                    level: "aji".to_owned(),
-                },
-                FriendInfo {
-                    name: "Doe".to_owned(),
-                    chance: default_chance::KI,
-                    level: "ki".to_owned(),
-                },
-                FriendInfo {
-                    name: "Doe2".to_owned(),
-                    chance: default_chance::CHI,
-                    level: "chi".to_owned(),
-                },
+                friend_info("John", "aji", default_chance::AJI),
+                friend_info("Doe", "ki", default_chance::KI),
+                friend_info("Doe2", "chi", default_chance::CHI),
             ],
+            ..Default::default()
         };
 
         decrease_chances_by_reduction(&mut config, 1.0, &["Doe".to_owned()]);
@@ -270,26 +838,54 @@ mod test {
         assert_eq!(config.friends[2].chance, default_chance::CHI);
     }
 
+    #[test]
+    fn test_recover_chances() {
+        let mut config = AkcConfig {
+            friends: vec![
+                friend_info("John", "aji", 10.0),
+                friend_info("Doe", "ki", 9.0),
+                friend_info("Jane", "chi", 3.0),
+            ],
+            levels: default_levels(),
+            ..Default::default()
+        };
+        let days_since_last_memory =
+            HashMap::from([("John".to_owned(), 30.0), ("Doe".to_owned(), 0.0)]);
+
+        recover_chances(&mut config, &days_since_last_memory);
+        assert_eq!(config.friends[0].chance, 30.0);
+        assert_eq!(config.friends[1].chance, 9.0);
+        assert_eq!(config.friends[2].chance, 3.0);
+    }
+
+    #[test]
+    fn test_apply_skip_penalties() {
+        let mut config = AkcConfig {
+            friends: vec![
+                friend_info("John", "ki", 5.0),
+                friend_info("Doe", "ki", 5.0),
+            ],
+            settings: Settings {
+                skip_penalty: 0.5,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+
+        apply_skip_penalties(&mut config, &HashMap::from([("John".to_owned(), 3)]));
+        assert_eq!(config.friends[0].chance, 3.5);
+        assert_eq!(config.friends[1].chance, 5.0);
+    }
+
     #[test]
     fn test_list_friends() {
         let config = AkcConfig {
             friends: vec![
-                FriendInfo {
-                    name: "John".to_owned(),
-                    chance: default_chance::AJI,
-                    level: "aji".to_owned(),
-                },
-                FriendInfo {
-                    name: "Doe".to_owned(),
-                    chance: default_chance::KI,
-                    level: "ki".to_owned(),
-                },
-                FriendInfo {
-                    name: "Doe2".to_owned(),
-                    chance: default_chance::CHI,
-                    level: "chi".to_owned(),
-                },
+                friend_info("John", "aji", default_chance::AJI),
+                friend_info("Doe", "ki", default_chance::KI),
+                friend_info("Doe2", "chi", default_chance::CHI),
             ],
+            ..Default::default()
         };
 
         let friends = list_friends(&config);
@@ -300,22 +896,11 @@ mod test {
     fn test_list_friends_by_chance() {
         let config = AkcConfig {
             friends: vec![
-                FriendInfo {
-                    name: "John".to_owned(),
-                    chance: 1.5,
-                    level: "aji".to_owned(),
-                },
-                FriendInfo {
-                    name: "Doe".to_owned(),
-                    chance: 5.0,
-                    level: "ki".to_owned(),
-                },
-                FriendInfo {
-                    name: "Abe".to_owned(),
-                    chance: 5.0,
-                    level: "chi".to_owned(),
-                },
+                friend_info("John", "aji", 1.5),
+                friend_info("Doe", "ki", 5.0),
+                friend_info("Abe", "chi", 5.0),
             ],
+            ..Default::default()
         };
 
         let friends = list_friends_by_chance(&config);
@@ -323,25 +908,193 @@ mod test {
     }
 
     #[test]
-    fn test_search_friends() {
-        let config = AkcConfig {
+    fn test_increase_chances_by_unit_with_custom_level() {
+        let mut config = AkcConfig {
             friends: vec![
-                FriendInfo {
-                    name: "John".to_owned(),
-                    chance: 1.5,
-                    level: "aji".to_owned(),
+                friend_info("John", "family", 10.0),
+                friend_info("Doe", "unknown", 1.0),
+            ],
+            levels: vec![LevelInfo {
+                name: "family".to_owned(),
+                chance: 10.0,
+            }],
+            ..Default::default()
+        };
+
+        increase_chances_by_unit(&mut config, 0.5, &[]);
+        assert_eq!(config.friends[0].chance, 15.0);
+        assert_eq!(config.friends[1].chance, 1.0);
+    }
+
+    #[test]
+    fn test_list_levels() {
+        let config = AkcConfig {
+            levels: default_levels(),
+            ..Default::default()
+        };
+
+        let levels = list_levels(&config);
+        assert_eq!(levels, "aji (50.00)\nki (5.00)\nchi (1.00)");
+    }
+
+    #[test]
+    fn test_list_kinds() {
+        let config = AkcConfig {
+            kinds: vec![
+                KindInfo {
+                    name: "text".to_owned(),
+                    reduction: default_reduction::TEXT,
                 },
-                FriendInfo {
-                    name: "Johnny".to_owned(),
-                    chance: 5.0,
-                    level: "ki".to_owned(),
+                KindInfo {
+                    name: "game night".to_owned(),
+                    reduction: default_reduction::HANGOUT,
                 },
-                FriendInfo {
-                    name: "Abe".to_owned(),
-                    chance: 5.0,
-                    level: "chi".to_owned(),
+                KindInfo {
+                    name: "hangout".to_owned(),
+                    reduction: default_reduction::HANGOUT,
                 },
             ],
+            ..Default::default()
+        };
+
+        let kinds = list_kinds(&config);
+        assert_eq!(kinds, "game night (2.00)\nhangout (2.00)\ntext (0.25)");
+    }
+
+    #[test]
+    fn test_list_defaults() {
+        let config = AkcConfig {
+            levels: default_levels(),
+            kinds: vec![KindInfo {
+                name: "text".to_owned(),
+                reduction: default_reduction::TEXT,
+            }],
+            ..Default::default()
+        };
+
+        let defaults = list_defaults(&config);
+        assert_eq!(
+            defaults,
+            "chance.aji = 50.00\nchance.chi = 1.00\nchance.ki = 5.00\nreduction.text = 0.25\nmin-chance = 0.25\nchance-model = zero-sum\nrecovery-half-life = 30.00\nskip-penalty = 0.00\nstrategy = weighted-random\nsoftmax-temperature = 10.00"
+        );
+    }
+
+    #[test]
+    fn test_list_constraints() {
+        let mut constraints = Constraints::default();
+        constraints.friends.insert(
+            "John".to_owned(),
+            ConnectionConstraint {
+                cooldown_days: Some(7),
+                interval_days: Some(30),
+            },
+        );
+        constraints.levels.insert(
+            "chi".to_owned(),
+            ConnectionConstraint {
+                cooldown_days: None,
+                interval_days: Some(60),
+            },
+        );
+
+        assert_eq!(
+            list_constraints(&constraint_records(&constraints)),
+            "friend John: cooldown 7 days, at least every 30 days\nlevel chi: at least every 60 days"
+        );
+    }
+
+    #[test]
+    fn test_sample_without_replacement() {
+        let friends = [
+            friend_info("John", "aji", 3.0),
+            friend_info("Doe", "aji", 1.0),
+            friend_info("Jane", "aji", 2.0),
+            friend_info("Zero", "aji", 0.0),
+        ];
+        let candidates = [friends.iter().collect::<Vec<&FriendInfo>>()];
+        let mut rng = ChaCha8Rng::seed_from_u64(0);
+        let mut sample =
+            |tiers: &[Vec<&FriendInfo>],
+             count: usize,
+             companions: Option<&HashMap<String, HashSet<String>>>| {
+                sample_without_replacement(tiers, count, companions, &mut WeightedRandom, &mut rng)
+                    .iter()
+                    .map(|friend| friend.name.clone())
+                    .collect::<Vec<String>>()
+            };
+
+        // Friends without weight are never drawn.
+        assert_eq!(sample(&candidates, 10, None), ["Jane", "John", "Doe"]);
+        assert_eq!(sample(&candidates, 2, None), ["John", "Jane"]);
+
+        let companions = HashMap::from([
+            ("John".to_owned(), HashSet::from(["Doe".to_owned()])),
+            ("Doe".to_owned(), HashSet::from(["John".to_owned()])),
+        ]);
+        assert_eq!(sample(&candidates, 3, Some(&companions)), ["John", "Doe"]);
+        assert_eq!(sample(&candidates, 3, Some(&companions)), ["Doe", "John"]);
+
+        // Overdue friends are drawn first, the remaining picks come from everyone else.
+        let tiers = [
+            vec![&friends[2]],
+            vec![&friends[0], &friends[1], &friends[3]],
+        ];
+        assert_eq!(sample(&tiers, 3, None), ["Jane", "Doe", "John"]);
+    }
+
+    #[test]
+    fn test_render_table() {
+        let rows = vec![
+            vec!["John".to_owned(), "aji".to_owned()],
+            vec!["Johnny".to_owned(), "".to_owned()],
+        ];
+
+        assert_eq!(
+            render_table(&["NAME", "LEVEL"], &rows),
+            "NAME    LEVEL\nJohn    aji\nJohnny"
+        );
+    }
+
+    #[test]
+    fn test_render_status() {
+        let statuses = vec![
+            FriendStatus {
+                name: "Doe".to_owned(),
+                level: "ki".to_owned(),
+                chance: 5.0,
+                probability: 0.0,
+                last_memory_date: None,
+                last_memory_kind: None,
+                memory_counts: BTreeMap::new(),
+            },
+            FriendStatus {
+                name: "John".to_owned(),
+                level: "aji".to_owned(),
+                chance: 48.0,
+                probability: 100.0,
+                last_memory_date: Some("2026-10-11".to_owned()),
+                last_memory_kind: Some("hangout".to_owned()),
+                memory_counts: BTreeMap::from([("call".to_owned(), 2), ("hangout".to_owned(), 1)]),
+            },
+        ];
+
+        assert_eq!(
+            render_status(statuses),
+            "NAME  LEVEL  CHANCE  PROBABILITY  LAST MEMORY  KIND     MEMORIES\n\
+             John  aji    48.00   100.0%       2026-10-11   hangout  call: 2, hangout: 1\n\
+             Doe   ki     5.00    0.0%         -            -        -"
+        );
+    }
+
+    #[test]
+    fn test_search_friends() {
+        let config = AkcConfig {
+            friends: vec![
+                friend_info("John", "aji", 1.5),
+                friend_info("Johnny", "ki", 5.0),
+                friend_info("Abe", "chi", 5.0),
+            ],
+            ..Default::default()
         };
 
         let friends = search_friends(&config, "joHn");
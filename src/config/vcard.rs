@@ -0,0 +1,189 @@
+use std::{fs, path::Path};
+
+use super::{
+    content_line::{
+        escape_value, fold_line, parse_content_line, unescape_list, unescape_value, unfold_lines,
+    },
+    friend_import::{import_friends, ImportedFriend},
+    open_pool, transfer,
+};
+
+/// Property holding the closeness level of an exported friend.
+const LEVEL_PROPERTY: &str = "X-AKC-LEVEL";
+
+/// The parts of a vCard that matter for friends.
+#[derive(Debug, Default, PartialEq)]
+struct VCard {
+    name: String,
+    level: Option<String>,
+    note: Option<String>,
+    tags: Vec<String>,
+}
+
+/// Parses every `VCARD` in `text`, skipping cards without a formatted name.
+fn parse_vcards(text: &str) -> Vec<VCard> {
+    let mut cards = Vec::new();
+    let mut card: Option<VCard> = None;
+    for line in unfold_lines(text) {
+        let Some(line) = parse_content_line(&line) else {
+            continue;
+        };
+        let value = &line.value;
+        match (line.name.as_str(), card.as_mut()) {
+            ("BEGIN", _) if value.eq_ignore_ascii_case("VCARD") => card = Some(VCard::default()),
+            ("END", Some(_)) if value.eq_ignore_ascii_case("VCARD") => {
+                cards.extend(card.take().filter(|card| !card.name.is_empty()))
+            }
+            ("FN", Some(card)) => card.name = unescape_value(value).trim().to_owned(),
+            ("NOTE", Some(card)) => card.note = Some(unescape_value(value)),
+            ("CATEGORIES", Some(card)) => card.tags.extend(unescape_list(value)),
+            (LEVEL_PROPERTY, Some(card)) => {
+                card.level = Some(unescape_value(value).trim().to_owned())
+            }
+            _ => {}
+        }
+    }
+    cards
+}
+
+/// Renders cards as vCard 3.0, keeping the level in an `X-AKC-LEVEL` property.
+fn render_vcards(cards: &[VCard]) -> String {
+    cards
+        .iter()
+        .map(|card| {
+            let mut lines = vec![
+                "BEGIN:VCARD".to_owned(),
+                "VERSION:3.0".to_owned(),
+                format!("FN:{}", escape_value(&card.name)),
+                format!("N:;{};;;", escape_value(&card.name)),
+            ];
+            if let Some(ref note) = card.note {
+                lines.push(format!("NOTE:{}", escape_value(note)));
+            }
+            if !card.tags.is_empty() {
+                let tags = card
+                    .tags
+                    .iter()
+                    .map(|tag| escape_value(tag))
+                    .collect::<Vec<String>>();
+                lines.push(format!("CATEGORIES:{}", tags.join(",")));
+            }
+            if let Some(ref level) = card.level {
+                lines.push(format!("{LEVEL_PROPERTY}:{}", escape_value(level)));
+            }
+            lines.push("END:VCARD".to_owned());
+            lines.iter().map(|line| fold_line(line)).collect::<String>()
+        })
+        .collect()
+}
+
+/// Imports friends from a vCard file, using `default_level` for cards without a level.
+pub async fn import_friends_vcard(path: &Path, default_level: Option<&str>, dry_run: bool) {
+    let text = match fs::read_to_string(path) {
+        Ok(text) => text,
+        Err(err) => {
+            eprintln!("Failed to read {}: {err}", path.display());
+            return;
+        }
+    };
+
+    let mut friends = Vec::new();
+    for (index, card) in parse_vcards(&text).into_iter().enumerate() {
+        let origin = format!("Card {}", index + 1);
+        let Some(level) = card.level.or_else(|| default_level.map(str::to_owned)) else {
+            println!("{origin}: No {LEVEL_PROPERTY} property, please pass --level");
+            return;
+        };
+        friends.push(ImportedFriend {
+            origin,
+            name: card.name,
+            level,
+            note: card.note,
+            tags: card.tags,
+        });
+    }
+
+    import_friends(friends, dry_run).await
+}
+
+/// Writes all friends as vCards to `out`, or to stdout if no file is given.
+pub async fn export_friends_vcard(out: Option<&Path>) {
+    let friends = match open_pool().await {
+        Ok(pool) => transfer::read_exported_friends(&pool).await,
+        Err(err) => Err(err),
+    };
+    let cards = match friends {
+        Ok(friends) => friends
+            .into_iter()
+            .map(|friend| VCard {
+                name: friend.name,
+                level: Some(friend.level),
+                note: friend.note,
+                tags: friend.tags,
+            })
+            .collect::<Vec<VCard>>(),
+        Err(err) => {
+            eprintln!("Failed to read data: {err}");
+            return;
+        }
+    };
+
+    let text = render_vcards(&cards);
+    match out {
+        Some(path) => {
+            if let Err(err) = fs::write(path, text) {
+                eprintln!("Failed to write {}: {err}", path.display());
+            }
+        }
+        None => print!("{text}"),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{parse_vcards, render_vcards, VCard};
+
+    #[test]
+    fn test_parse_vcards() {
+        let text = "BEGIN:VCARD\r\nVERSION:3.0\r\nitem1.FN;CHARSET=UTF-8:Doe\\, John\r\n\
+            CATEGORIES:work,book club\r\nEND:VCARD\r\n\
+            BEGIN:VCARD\r\nN:;Nameless;;;\r\nEND:VCARD\r\n\
+            BEGIN:VCARD\r\nFN:Negar\r\nx-akc-level:aji\r\nNOTE:Line\\nbreak\r\nEND:VCARD\r\n";
+
+        assert_eq!(
+            parse_vcards(text),
+            vec![
+                VCard {
+                    name: "Doe, John".to_owned(),
+                    tags: vec!["work".to_owned(), "book club".to_owned()],
+                    ..Default::default()
+                },
+                VCard {
+                    name: "Negar".to_owned(),
+                    level: Some("aji".to_owned()),
+                    note: Some("Line\nbreak".to_owned()),
+                    ..Default::default()
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_vcards_round_trip() {
+        let cards = vec![
+            VCard {
+                name: "Doe; John".to_owned(),
+                level: Some("close, family".to_owned()),
+                note: Some("Met at uni\nin 2015".to_owned()),
+                tags: vec!["work".to_owned(), "a,b".to_owned()],
+            },
+            VCard {
+                name: "Negar".to_owned(),
+                level: Some("aji".to_owned()),
+                ..Default::default()
+            },
+        ];
+
+        assert_eq!(parse_vcards(&render_vcards(&cards)), cards);
+    }
+}
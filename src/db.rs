@@ -0,0 +1,32 @@
+use clap::{Args, Parser, Subcommand};
+
+use crate::config;
+
+#[derive(Args)]
+pub struct MigrateCommand {
+    /// Only show applied and pending migrations
+    #[arg(long)]
+    status: bool,
+}
+
+#[derive(Subcommand)]
+#[command(about = "Manage the database")]
+/// Database-related subcommands.
+pub enum DbCommand {
+    Migrate(MigrateCommand),
+}
+
+#[derive(Parser)]
+/// Root wrapper for `akc db ...`.
+pub struct Db {
+    #[command(subcommand)]
+    command: DbCommand,
+}
+
+/// Executes parsed database commands.
+pub async fn handle(args: Db) {
+    match args.command {
+        DbCommand::Migrate(args) if args.status => config::print_migration_status().await,
+        DbCommand::Migrate(_) => config::migrate_database().await,
+    }
+}
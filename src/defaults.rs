@@ -0,0 +1,42 @@
+use clap::{Args, Parser, Subcommand};
+
+use crate::config;
+
+#[derive(Args)]
+pub struct SetDefaultCommand {
+    /// `chance.<level>`, `reduction.<kind>`, `min-chance`, `chance-model`,
+    /// `recovery-half-life`, `skip-penalty`, `strategy` or `softmax-temperature`
+    key: String,
+    value: String,
+}
+
+#[derive(Args)]
+pub struct ResetDefaultsCommand {
+    /// Resets only this key instead of all of them
+    key: Option<String>,
+}
+
+#[derive(Subcommand)]
+#[command(about = "Show or change base chances, reductions and other defaults")]
+/// Defaults-related subcommands.
+pub enum DefaultsCommand {
+    Show,
+    Set(SetDefaultCommand),
+    Reset(ResetDefaultsCommand),
+}
+
+#[derive(Parser)]
+/// Root wrapper for `akc defaults ...`.
+pub struct Defaults {
+    #[command(subcommand)]
+    command: DefaultsCommand,
+}
+
+/// Executes parsed defaults commands.
+pub async fn handle(args: Defaults) {
+    match args.command {
+        DefaultsCommand::Show => config::show_defaults().await,
+        DefaultsCommand::Set(args) => config::set_default(args.key, args.value).await,
+        DefaultsCommand::Reset(args) => config::reset_defaults(args.key).await,
+    }
+}
@@ -0,0 +1,18 @@
+use clap::Parser;
+use std::path::PathBuf;
+
+use crate::config;
+
+#[derive(Parser)]
+#[command(about = "Exports all data to a portable JSON file")]
+/// Command wrapper for `akc export`.
+pub struct ExportCommand {
+    /// File to write to instead of stdout
+    #[arg(long)]
+    out: Option<PathBuf>,
+}
+
+/// Runs export flow.
+pub async fn handle(args: ExportCommand) {
+    config::export_data(args.out.as_deref()).await
+}
@@ -1,4 +1,5 @@
-use clap::{Args, Parser, Subcommand, ValueEnum};
+use clap::{Args, CommandFactory, Parser, Subcommand};
+use std::path::PathBuf;
 
 use crate::config;
 
@@ -7,12 +8,13 @@ pub struct FriendCommandBase {
     name: String,
 }
 
-#[derive(ValueEnum, Clone)]
-/// Supported friend closeness levels.
-pub enum FriendLevel {
-    Aji,
-    Ki,
-    Chi,
+#[derive(Parser)]
+/// Arguments of `akc friend <level> ...`, parsed after the level name.
+struct AddFriendCommand {
+    name: String,
+    /// Output format, accepted like on every other command although adding prints no records
+    #[arg(long = "format", value_enum)]
+    _format: Option<config::OutputFormat>,
 }
 
 #[derive(Args)]
@@ -21,13 +23,13 @@ pub struct EditFriendCommand {
     #[arg(long)]
     new_name: Option<String>,
     #[arg(long)]
-    level: Option<FriendLevel>,
+    level: Option<String>,
 }
 
 #[derive(Args)]
 pub struct ListFriendsCommand {
     #[arg(long = "type")]
-    friend_type: Option<FriendLevel>,
+    friend_type: Option<String>,
     #[arg(long = "sort-chance")]
     sort_chance: bool,
 }
@@ -39,30 +41,113 @@ pub struct SearchFriendsCommand {
 
 #[derive(Args)]
 pub struct AddManyFriendsCommand {
-    level: FriendLevel,
+    level: String,
     names: Vec<String>,
 }
 
+#[derive(Args)]
+pub struct ImportFriendsCommand {
+    /// CSV file with name and level columns, and optionally notes and tags columns, or a vCard
+    /// file with --vcard
+    file: PathBuf,
+    /// Read the file as vCards instead of CSV
+    #[arg(long)]
+    vcard: bool,
+    /// Level for vCards without an X-AKC-LEVEL property
+    #[arg(long, requires = "vcard")]
+    level: Option<String>,
+    /// Only show what would be imported
+    #[arg(long)]
+    dry_run: bool,
+}
+
+#[derive(Args)]
+pub struct ExportFriendsCommand {
+    /// Write friends as vCards, with their level in an X-AKC-LEVEL property
+    #[arg(long, required = true)]
+    vcard: bool,
+    /// File to write to instead of stdout
+    #[arg(long)]
+    out: Option<PathBuf>,
+}
+
 #[derive(Args)]
 pub struct ChangeTypeCommand {
     name: String,
-    level: FriendLevel,
+    level: String,
+}
+
+#[derive(Args)]
+#[group(required = true, multiple = false)]
+pub struct ConstraintTargetArgs {
+    #[arg(long)]
+    friend: Option<String>,
+    #[arg(long)]
+    level: Option<String>,
+}
+
+impl From<ConstraintTargetArgs> for config::ConstraintTarget {
+    fn from(args: ConstraintTargetArgs) -> Self {
+        match (args.friend, args.level) {
+            (Some(name), _) => config::ConstraintTarget::Friend(name),
+            (None, Some(level)) => config::ConstraintTarget::Level(level),
+            (None, None) => unreachable!("clap requires either --friend or --level"),
+        }
+    }
+}
+
+#[derive(Args)]
+pub struct SetConstraintCommand {
+    #[command(flatten)]
+    target: ConstraintTargetArgs,
+    /// Don't suggest within this many days of any memory
+    #[arg(long, value_parser = clap::value_parser!(i64).range(1..))]
+    cooldown: Option<i64>,
+    /// Suggest at least once every this many days
+    #[arg(long, value_parser = clap::value_parser!(i64).range(1..))]
+    every: Option<i64>,
+}
+
+#[derive(Args)]
+pub struct ClearConstraintCommand {
+    #[command(flatten)]
+    target: ConstraintTargetArgs,
 }
 
 #[derive(Subcommand)]
-#[command(about = "Add or list friends")]
+#[command(about = "Set, clear or show connection constraints")]
+/// Constraint-related subcommands.
+pub enum ConstraintCommand {
+    Set(SetConstraintCommand),
+    Clear(ClearConstraintCommand),
+    Show,
+}
+
+#[derive(Args)]
+pub struct ConstraintCommandWrapper {
+    #[command(subcommand)]
+    command: ConstraintCommand,
+}
+
+#[derive(Subcommand)]
+#[command(
+    about = "Add or list friends",
+    override_usage = "akc friend <LEVEL> <NAME>\n       akc friend <COMMAND>"
+)]
 /// Friend-related subcommands.
 pub enum FriendCommand {
-    Aji(FriendCommandBase),
-    Ki(FriendCommandBase),
-    Chi(FriendCommandBase),
     AddMany(AddManyFriendsCommand),
+    Import(ImportFriendsCommand),
+    Export(ExportFriendsCommand),
     Type(ChangeTypeCommand),
     Rm(FriendCommandBase),
     Edit(EditFriendCommand),
     Search(SearchFriendsCommand),
     #[command(alias = "ls")]
     List(ListFriendsCommand),
+    Constraint(ConstraintCommandWrapper),
+    #[command(external_subcommand)]
+    Add(Vec<String>),
 }
 
 #[derive(Parser)]
@@ -73,44 +158,54 @@ pub struct Friend {
 }
 
 /// Executes parsed friend commands.
-pub async fn handle(args: Friend) {
+pub async fn handle(args: Friend, format: config::OutputFormat) {
     match args.command {
-        FriendCommand::Aji(name_wrapper) => config::add_aji(name_wrapper.name).await,
-        FriendCommand::Ki(name_wrapper) => config::add_ki(name_wrapper.name).await,
-        FriendCommand::Chi(name_wrapper) => config::add_chi(name_wrapper.name).await,
-        FriendCommand::AddMany(args) => {
-            let level = match args.level {
-                FriendLevel::Aji => "aji".to_owned(),
-                FriendLevel::Ki => "ki".to_owned(),
-                FriendLevel::Chi => "chi".to_owned(),
-            };
-            config::add_many_friends(level, args.names).await
+        FriendCommand::Add(mut args) => {
+            let level = args[0].clone();
+            args[0] = format!("akc friend {level}");
+            let add_args = AddFriendCommand::parse_from(args);
+            config::add_friend(level, add_args.name).await
         }
-        FriendCommand::Type(args) => {
-            let level = match args.level {
-                FriendLevel::Aji => "aji".to_owned(),
-                FriendLevel::Ki => "ki".to_owned(),
-                FriendLevel::Chi => "chi".to_owned(),
-            };
-            config::edit_friend(args.name, None, Some(level)).await
+        FriendCommand::AddMany(args) => config::add_many_friends(args.level, args.names).await,
+        FriendCommand::Import(args) if args.vcard => {
+            config::import_friends_vcard(&args.file, args.level.as_deref(), args.dry_run).await
         }
+        FriendCommand::Import(args) => config::import_friends_csv(&args.file, args.dry_run).await,
+        FriendCommand::Export(args) => config::export_friends_vcard(args.out.as_deref()).await,
+        FriendCommand::Type(args) => config::edit_friend(args.name, None, Some(args.level)).await,
         FriendCommand::Rm(name_wrapper) => config::remove_friend(name_wrapper.name).await,
         FriendCommand::Edit(args) => {
-            let level = args.level.map(|level| match level {
-                FriendLevel::Aji => "aji".to_owned(),
-                FriendLevel::Ki => "ki".to_owned(),
-                FriendLevel::Chi => "chi".to_owned(),
-            });
-            config::edit_friend(args.name, args.new_name, level).await
+            config::edit_friend(args.name, args.new_name, args.level).await
         }
-        FriendCommand::Search(args) => config::search_friends(args.query).await,
+        FriendCommand::Search(args) => config::search_friends(args.query, format).await,
         FriendCommand::List(args) => {
-            let friend_type = args.friend_type.map(|level| match level {
-                FriendLevel::Aji => "aji".to_owned(),
-                FriendLevel::Ki => "ki".to_owned(),
-                FriendLevel::Chi => "chi".to_owned(),
-            });
-            config::list_friends(friend_type, args.sort_chance).await
+            config::list_friends(args.friend_type, args.sort_chance, format).await
         }
+        FriendCommand::Constraint(args) => match args.command {
+            ConstraintCommand::Set(args) => {
+                config::set_constraint(args.target.into(), args.cooldown, args.every).await
+            }
+            ConstraintCommand::Clear(args) => config::clear_constraint(args.target.into()).await,
+            ConstraintCommand::Show => config::show_constraints(format).await,
+        },
+    }
+}
+
+/// Returns whether `name` would be shadowed by a built-in friend subcommand.
+pub fn is_reserved_name(name: &str) -> bool {
+    Friend::command().find_subcommand(name).is_some()
+}
+
+#[cfg(test)]
+mod test {
+    use super::is_reserved_name;
+    use crate::config::DEFAULT_LEVELS;
+
+    #[test]
+    fn test_default_levels_are_not_reserved() {
+        assert!(is_reserved_name("add-many"));
+        assert!(!DEFAULT_LEVELS
+            .iter()
+            .any(|(name, _)| is_reserved_name(name)));
     }
 }
@@ -0,0 +1,33 @@
+use clap::Parser;
+use std::path::PathBuf;
+
+use crate::{config, friend, memory};
+
+#[derive(Parser)]
+#[command(about = "Imports data exported by `akc export`")]
+/// Command wrapper for `akc import`.
+pub struct ImportCommand {
+    file: PathBuf,
+    /// Keep existing data and add what's missing (default)
+    #[arg(long, conflicts_with = "replace")]
+    merge: bool,
+    /// Delete existing data before importing
+    #[arg(long)]
+    replace: bool,
+}
+
+/// Runs import flow.
+pub async fn handle(args: ImportCommand) {
+    let mode = if args.replace {
+        config::ImportMode::Replace
+    } else {
+        config::ImportMode::Merge
+    };
+    config::import_data(
+        &args.file,
+        mode,
+        friend::is_reserved_name,
+        memory::is_reserved_name,
+    )
+    .await
+}
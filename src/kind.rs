@@ -0,0 +1,70 @@
+use clap::{Args, Parser, Subcommand};
+
+use crate::{config, memory};
+
+#[derive(Args)]
+pub struct KindCommandBase {
+    name: String,
+}
+
+#[derive(Args)]
+pub struct AddKindCommand {
+    name: String,
+    reduction: f64,
+}
+
+#[derive(Args)]
+pub struct EditKindCommand {
+    name: String,
+    #[arg(long)]
+    new_name: Option<String>,
+    #[arg(long)]
+    reduction: Option<f64>,
+}
+
+#[derive(Subcommand)]
+#[command(about = "Add, edit or list memory kinds")]
+/// Memory kind subcommands.
+pub enum KindCommand {
+    Add(AddKindCommand),
+    Edit(EditKindCommand),
+    Rm(KindCommandBase),
+    #[command(alias = "ls")]
+    List,
+}
+
+#[derive(Parser)]
+/// Root wrapper for `akc kind ...`.
+pub struct Kind {
+    #[command(subcommand)]
+    command: KindCommand,
+}
+
+/// Executes parsed memory kind commands.
+pub async fn handle(args: Kind, format: config::OutputFormat) {
+    match args.command {
+        KindCommand::Add(args) => {
+            if memory::is_reserved_name(&args.name) {
+                println!(
+                    "Memory kind name \"{}\" is reserved, please use a different name",
+                    args.name
+                );
+                return;
+            }
+            config::add_kind(args.name, args.reduction).await
+        }
+        KindCommand::Edit(args) => {
+            if let Some(ref new_name) = args.new_name {
+                if memory::is_reserved_name(new_name) {
+                    println!(
+                        "Memory kind name \"{new_name}\" is reserved, please use a different name"
+                    );
+                    return;
+                }
+            }
+            config::edit_kind(args.name, args.new_name, args.reduction).await
+        }
+        KindCommand::Rm(name_wrapper) => config::remove_kind(name_wrapper.name).await,
+        KindCommand::List => config::list_kinds(format).await,
+    }
+}
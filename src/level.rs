@@ -0,0 +1,68 @@
+use clap::{Args, Parser, Subcommand};
+
+use crate::{config, friend};
+
+#[derive(Args)]
+pub struct LevelCommandBase {
+    name: String,
+}
+
+#[derive(Args)]
+pub struct AddLevelCommand {
+    name: String,
+    chance: f64,
+}
+
+#[derive(Args)]
+pub struct EditLevelCommand {
+    name: String,
+    #[arg(long)]
+    new_name: Option<String>,
+    #[arg(long)]
+    chance: Option<f64>,
+}
+
+#[derive(Subcommand)]
+#[command(about = "Add, edit or list closeness levels")]
+/// Level-related subcommands.
+pub enum LevelCommand {
+    Add(AddLevelCommand),
+    Edit(EditLevelCommand),
+    Rm(LevelCommandBase),
+    #[command(alias = "ls")]
+    List,
+}
+
+#[derive(Parser)]
+/// Root wrapper for `akc level ...`.
+pub struct Level {
+    #[command(subcommand)]
+    command: LevelCommand,
+}
+
+/// Executes parsed level commands.
+pub async fn handle(args: Level, format: config::OutputFormat) {
+    match args.command {
+        LevelCommand::Add(args) => {
+            if friend::is_reserved_name(&args.name) {
+                println!(
+                    "Level name \"{}\" is reserved, please use a different name",
+                    args.name
+                );
+                return;
+            }
+            config::add_level(args.name, args.chance).await
+        }
+        LevelCommand::Edit(args) => {
+            if let Some(ref new_name) = args.new_name {
+                if friend::is_reserved_name(new_name) {
+                    println!("Level name \"{new_name}\" is reserved, please use a different name");
+                    return;
+                }
+            }
+            config::edit_level(args.name, args.new_name, args.chance).await
+        }
+        LevelCommand::Rm(name_wrapper) => config::remove_level(name_wrapper.name).await,
+        LevelCommand::List => config::list_levels(format).await,
+    }
+}
@@ -1,18 +1,45 @@
-use clap::{CommandFactory, Parser};
+use clap::{CommandFactory, Parser, Subcommand};
 use clap_complete::{generate, Shell};
 use std::io;
 
 mod config;
+mod db;
+mod defaults;
+mod export;
 mod friend;
+mod import;
+mod kind;
+mod level;
 mod memory;
+mod plan;
+mod report;
+mod status;
 mod suggest;
 
 #[derive(Parser)]
 #[command(about, version)]
+struct Akc {
+    /// Output format of commands that print lists or records
+    #[arg(long, global = true, value_enum, default_value_t)]
+    format: config::OutputFormat,
+    #[command(subcommand)]
+    command: AkcCommand,
+}
+
+#[derive(Subcommand)]
 enum AkcCommand {
     Friend(friend::Friend),
+    Level(level::Level),
     Suggest(suggest::SuggestCommand),
+    Status(status::StatusCommand),
+    Report(report::Report),
     Memory(memory::Memory),
+    Plan(plan::Plan),
+    Kind(kind::Kind),
+    Defaults(defaults::Defaults),
+    Db(db::Db),
+    Export(export::ExportCommand),
+    Import(import::ImportCommand),
     DbPath,
     Completion(CompletionCommand),
 }
@@ -24,15 +51,25 @@ struct CompletionCommand {
 
 #[tokio::main]
 async fn main() {
-    let args = AkcCommand::parse();
+    let args = Akc::parse();
+    let format = args.format;
 
-    match args {
-        AkcCommand::Friend(friend_args) => friend::handle(friend_args).await,
-        AkcCommand::Suggest(_) => suggest::handle().await,
-        AkcCommand::Memory(memory_args) => memory::handle(memory_args).await,
+    match args.command {
+        AkcCommand::Friend(friend_args) => friend::handle(friend_args, format).await,
+        AkcCommand::Level(level_args) => level::handle(level_args, format).await,
+        AkcCommand::Suggest(suggest_args) => suggest::handle(suggest_args, format).await,
+        AkcCommand::Status(_) => status::handle(format).await,
+        AkcCommand::Report(report_args) => report::handle(report_args, format).await,
+        AkcCommand::Memory(memory_args) => memory::handle(memory_args, format).await,
+        AkcCommand::Plan(plan_args) => plan::handle(plan_args, format).await,
+        AkcCommand::Kind(kind_args) => kind::handle(kind_args, format).await,
+        AkcCommand::Defaults(defaults_args) => defaults::handle(defaults_args).await,
+        AkcCommand::Db(db_args) => db::handle(db_args).await,
+        AkcCommand::Export(export_args) => export::handle(export_args).await,
+        AkcCommand::Import(import_args) => import::handle(import_args).await,
         AkcCommand::DbPath => config::print_db_path(),
         AkcCommand::Completion(args) => {
-            generate(args.shell, &mut AkcCommand::command(), "akc", &mut io::stdout())
+            generate(args.shell, &mut Akc::command(), "akc", &mut io::stdout())
         }
     }
 }
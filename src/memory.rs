@@ -1,10 +1,32 @@
-use clap::{Args, Parser, Subcommand};
+use clap::{Args, CommandFactory, Parser, Subcommand};
+use std::path::PathBuf;
 
 use crate::config;
 
-#[derive(Args)]
-pub struct MemoryCommandBase {
+#[derive(Parser)]
+/// Arguments of `akc memory <kind> ...`, parsed after the kind name.
+struct AddMemoryCommand {
+    /// When the memory happened (in UTC): YYYY-MM-DD [HH:MM], "yesterday" or "3 days ago"
+    #[arg(long, visible_alias = "at", value_parser = parse_date)]
+    date: Option<config::DateInput>,
+    /// A note about the memory
+    #[arg(long)]
+    note: Option<String>,
+    /// Where the memory happened
+    #[arg(long)]
+    place: Option<String>,
+    /// A free-form tag, can be repeated
+    #[arg(long = "tag")]
+    tags: Vec<String>,
     names: Vec<String>,
+    /// Output format, accepted like on every other command although adding prints no records
+    #[arg(long = "format", value_enum)]
+    _format: Option<config::OutputFormat>,
+}
+
+fn parse_date(value: &str) -> Result<config::DateInput, String> {
+    config::parse_date_input(value)
+        .ok_or_else(|| "expected YYYY-MM-DD [HH:MM], \"yesterday\" or \"N days ago\"".to_owned())
 }
 
 #[derive(Args)]
@@ -12,17 +34,112 @@ pub struct MemoryIdCommandBase {
     id: i64,
 }
 
+#[derive(Args)]
+pub struct ListMemoriesCommand {
+    /// Only show memories with this friend
+    #[arg(long)]
+    friend: Option<String>,
+    /// Only show memories of this kind
+    #[arg(long)]
+    kind: Option<String>,
+    /// Only show memories on or after this date: YYYY-MM-DD, "yesterday" or "7 days ago"
+    #[arg(long, value_parser = parse_date)]
+    since: Option<config::DateInput>,
+    /// Only show memories on or before this date: YYYY-MM-DD, "yesterday" or "7 days ago"
+    #[arg(long, value_parser = parse_date)]
+    until: Option<config::DateInput>,
+    /// Show at most this many memories
+    #[arg(long, value_parser = clap::value_parser!(i64).range(1..))]
+    limit: Option<i64>,
+    /// Skip this many of the newest matching memories
+    #[arg(long, value_parser = clap::value_parser!(i64).range(0..))]
+    offset: Option<i64>,
+}
+
+#[derive(Args)]
+pub struct EditMemoryCommand {
+    id: i64,
+    /// New kind of the memory
+    #[arg(long)]
+    kind: Option<String>,
+    /// New participants of the memory
+    #[arg(long, num_args = 1..)]
+    names: Option<Vec<String>>,
+    /// New date of the memory (in UTC): YYYY-MM-DD [HH:MM], "yesterday" or "3 days ago"
+    #[arg(long, visible_alias = "at", value_parser = parse_date)]
+    date: Option<config::DateInput>,
+    /// New note of the memory, an empty note removes it
+    #[arg(long)]
+    note: Option<String>,
+}
+
+#[derive(Args)]
+pub struct SearchMemoriesCommand {
+    /// Text to find in notes, places and tags
+    text: String,
+    #[command(flatten)]
+    filter: ListMemoriesCommand,
+}
+
+#[derive(Args)]
+pub struct ImportIcsCommand {
+    /// iCalendar file whose past events are added as memories
+    file: PathBuf,
+    /// JSON file mapping event categories to memory kinds, e.g. {"Sport": "climbing"}
+    #[arg(long)]
+    mapping: Option<PathBuf>,
+    /// Memory kind for events without a mapped category
+    #[arg(long)]
+    kind: Option<String>,
+    /// Only show what would be imported
+    #[arg(long)]
+    dry_run: bool,
+}
+
+impl From<ListMemoriesCommand> for config::MemoryFilter {
+    fn from(args: ListMemoriesCommand) -> Self {
+        config::MemoryFilter {
+            text: None,
+            friend: args.friend,
+            kind: args.kind,
+            since: args.since,
+            until: args.until,
+            limit: args.limit,
+            offset: args.offset,
+        }
+    }
+}
+
 #[derive(Subcommand)]
-#[command(about = "Add a memory with one or more friends")]
+/// Commands for undone memories.
+pub enum TrashCommand {
+    /// List undone memories
+    #[command(alias = "ls")]
+    List,
+    /// Permanently delete undone memories
+    Purge,
+}
+
+#[derive(Subcommand)]
+#[command(
+    about = "Add a memory with one or more friends",
+    override_usage = "akc memory <KIND> [NAMES]...\n       akc memory <COMMAND>"
+)]
 /// Memory-related subcommands.
 pub enum MemoryCommand {
-    Hangout(MemoryCommandBase),
-    VideoCall(MemoryCommandBase),
-    Call(MemoryCommandBase),
-    Text(MemoryCommandBase),
     Suggest,
     Undo,
+    Redo,
+    #[command(subcommand)]
+    Trash(TrashCommand),
     Remove(MemoryIdCommandBase),
+    Edit(EditMemoryCommand),
+    #[command(alias = "ls")]
+    List(ListMemoriesCommand),
+    Search(SearchMemoriesCommand),
+    ImportIcs(ImportIcsCommand),
+    #[command(external_subcommand)]
+    Add(Vec<String>),
 }
 
 #[derive(Parser)]
@@ -33,16 +150,70 @@ pub struct Memory {
 }
 
 /// Executes parsed memory commands.
-pub async fn handle(args: Memory) {
+pub async fn handle(args: Memory, format: config::OutputFormat) {
     match args.command {
-        MemoryCommand::Hangout(names_wrapper) => config::add_hangout(&names_wrapper.names).await,
-        MemoryCommand::VideoCall(names_wrapper) => {
-            config::add_video_call(&names_wrapper.names).await
+        MemoryCommand::Add(mut args) => {
+            let kind = args[0].clone();
+            args[0] = format!("akc memory {kind}");
+            let add_args = AddMemoryCommand::parse_from(args);
+            let details = config::MemoryDetails {
+                note: add_args.note,
+                place: add_args.place,
+                tags: add_args.tags,
+            };
+            config::add_memory(&kind, &add_args.names, add_args.date, details).await;
+        }
+        MemoryCommand::Suggest => {
+            config::suggest(
+                Default::default(),
+                format,
+                &mut config::suggestion_rng(None),
+            )
+            .await
         }
-        MemoryCommand::Call(names_wrapper) => config::add_call(&names_wrapper.names).await,
-        MemoryCommand::Text(names_wrapper) => config::add_text(&names_wrapper.names).await,
-        MemoryCommand::Suggest => config::suggest().await,
         MemoryCommand::Undo => config::undo_memory().await,
+        MemoryCommand::Redo => config::redo_memory().await,
+        MemoryCommand::Trash(TrashCommand::List) => config::list_trash(format).await,
+        MemoryCommand::Trash(TrashCommand::Purge) => config::purge_trash().await,
         MemoryCommand::Remove(args) => config::remove_memory(args.id).await,
+        MemoryCommand::Edit(args) => {
+            let edit = config::MemoryEdit {
+                kind: args.kind,
+                names: args.names,
+                date: args.date,
+                note: args.note,
+            };
+            config::edit_memory(args.id, edit).await
+        }
+        MemoryCommand::List(args) => config::list_memories(args.into(), format).await,
+        MemoryCommand::Search(args) => {
+            config::search_memories(args.text, args.filter.into(), format).await
+        }
+        MemoryCommand::ImportIcs(args) => {
+            config::import_ics(
+                &args.file,
+                args.mapping.as_deref(),
+                args.kind.as_deref(),
+                args.dry_run,
+            )
+            .await
+        }
+    }
+}
+
+/// Returns whether `name` would be shadowed by a built-in memory subcommand.
+pub fn is_reserved_name(name: &str) -> bool {
+    Memory::command().find_subcommand(name).is_some()
+}
+
+#[cfg(test)]
+mod test {
+    use super::is_reserved_name;
+    use crate::config::DEFAULT_KINDS;
+
+    #[test]
+    fn test_default_kinds_are_not_reserved() {
+        assert!(is_reserved_name("undo"));
+        assert!(!DEFAULT_KINDS.iter().any(|(name, _)| is_reserved_name(name)));
     }
 }
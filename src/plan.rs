@@ -0,0 +1,72 @@
+use clap::{Args, Parser, Subcommand};
+use std::path::PathBuf;
+
+use crate::config;
+
+#[derive(Args)]
+pub struct AddPlanCommand {
+    /// Friends to meet
+    #[arg(required = true)]
+    names: Vec<String>,
+    /// Kind of the memory this plan turns into
+    #[arg(long)]
+    kind: String,
+    /// When the plan is due (in UTC): YYYY-MM-DD [HH:MM]
+    #[arg(long, value_parser = parse_on)]
+    on: String,
+    /// A note about the plan
+    #[arg(long)]
+    note: Option<String>,
+}
+
+fn parse_on(value: &str) -> Result<String, String> {
+    config::parse_plan_date(value).ok_or_else(|| "expected YYYY-MM-DD [HH:MM]".to_owned())
+}
+
+#[derive(Args)]
+pub struct PlanIdCommandBase {
+    id: i64,
+}
+
+#[derive(Args)]
+pub struct ExportPlansCommand {
+    /// Write plans as iCalendar events
+    #[arg(long, required = true)]
+    ics: bool,
+    /// File to write to instead of stdout
+    #[arg(long)]
+    out: Option<PathBuf>,
+}
+
+#[derive(Subcommand)]
+#[command(about = "Plan future memories with friends")]
+/// Plan subcommands.
+pub enum PlanCommand {
+    Add(AddPlanCommand),
+    #[command(alias = "ls")]
+    List,
+    /// Record a plan as a memory
+    Done(PlanIdCommandBase),
+    Rm(PlanIdCommandBase),
+    Export(ExportPlansCommand),
+}
+
+#[derive(Parser)]
+/// Root wrapper for `akc plan ...`.
+pub struct Plan {
+    #[command(subcommand)]
+    command: PlanCommand,
+}
+
+/// Executes parsed plan commands.
+pub async fn handle(args: Plan, format: config::OutputFormat) {
+    match args.command {
+        PlanCommand::Add(args) => {
+            config::add_plan(&args.kind, &args.names, &args.on, args.note.as_deref()).await
+        }
+        PlanCommand::List => config::list_plans(format).await,
+        PlanCommand::Done(args) => config::complete_plan(args.id).await,
+        PlanCommand::Rm(args) => config::remove_plan(args.id).await,
+        PlanCommand::Export(args) => config::export_plans_ics(args.out.as_deref()).await,
+    }
+}
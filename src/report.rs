@@ -0,0 +1,25 @@
+use clap::{Parser, Subcommand};
+
+use crate::config;
+
+#[derive(Subcommand)]
+#[command(about = "Reports on suggestions and memories")]
+/// Report subcommands.
+pub enum ReportCommand {
+    /// Compare how often friends and levels are suggested with the memories shared
+    Fairness,
+}
+
+#[derive(Parser)]
+/// Root wrapper for `akc report ...`.
+pub struct Report {
+    #[command(subcommand)]
+    command: ReportCommand,
+}
+
+/// Executes parsed report commands.
+pub async fn handle(args: Report, format: config::OutputFormat) {
+    match args.command {
+        ReportCommand::Fairness => config::print_fairness_report(format).await,
+    }
+}
@@ -0,0 +1,13 @@
+use clap::Parser;
+
+use crate::config;
+
+#[derive(Parser)]
+#[command(about = "Shows connection status of all friends")]
+/// Command wrapper for `akc status`.
+pub struct StatusCommand {}
+
+/// Runs status flow.
+pub async fn handle(format: config::OutputFormat) {
+    config::print_status(format).await
+}
@@ -1,13 +1,95 @@
-use clap::Parser;
+use clap::{Args, Parser, Subcommand};
 
 use crate::config;
 
+#[derive(Args)]
+pub struct SuggestionHistoryCommand {
+    /// Only list suggestions of this friend
+    #[arg(long)]
+    friend: Option<String>,
+    /// List at most this many suggestions
+    #[arg(long, value_parser = clap::value_parser!(i64).range(1..))]
+    limit: Option<i64>,
+}
+
+#[derive(Subcommand)]
+/// Suggest subcommands.
+pub enum SuggestSubcommand {
+    /// List past suggestions, newest first
+    History(SuggestionHistoryCommand),
+}
+
 #[derive(Parser)]
-#[command(about = "Suggests a friend to connect with randomly")]
+#[command(
+    about = "Suggests a friend to connect with randomly",
+    args_conflicts_with_subcommands = true
+)]
 /// Command wrapper for `akc suggest`.
-pub struct SuggestCommand {}
+pub struct SuggestCommand {
+    #[command(subcommand)]
+    command: Option<SuggestSubcommand>,
+    /// Suggest this many distinct friends
+    #[arg(short = 'n', long, value_parser = clap::value_parser!(u64).range(1..))]
+    count: Option<u64>,
+    /// Only suggest friends who have all shared a memory with each other
+    #[arg(long, requires = "count")]
+    group: bool,
+    /// Only suggest friends of this level
+    #[arg(long)]
+    level: Option<String>,
+    /// Only suggest friends with this tag
+    #[arg(long)]
+    tag: Option<String>,
+    /// Never suggest this friend, can be repeated
+    #[arg(long)]
+    exclude: Vec<String>,
+    /// Only suggest friends whose chance covers the reduction of this memory kind
+    #[arg(long = "for", value_name = "KIND")]
+    for_kind: Option<String>,
+    /// Ask whether to accept, skip or snooze each suggestion
+    #[arg(short, long, conflicts_with_all = ["count", "group"])]
+    interactive: bool,
+    /// Seed of the random draw, the same seed and data give the same suggestion
+    #[arg(long)]
+    seed: Option<u64>,
+    /// How to pick friends, instead of the `strategy` default
+    #[arg(long, value_enum)]
+    strategy: Option<config::StrategyKind>,
+    /// Softmax temperature, instead of the `softmax-temperature` default, only for the softmax
+    /// strategy
+    #[arg(long, value_parser = parse_temperature)]
+    temperature: Option<f64>,
+}
+
+fn parse_temperature(value: &str) -> Result<f64, String> {
+    value
+        .parse::<f64>()
+        .ok()
+        .filter(|temperature| temperature.is_finite() && *temperature > 0.0)
+        .ok_or_else(|| "expected a positive number".to_owned())
+}
 
 /// Runs suggestion flow.
-pub async fn handle() {
-    config::suggest().await
+pub async fn handle(args: SuggestCommand, format: config::OutputFormat) {
+    if let Some(SuggestSubcommand::History(args)) = args.command {
+        return config::list_suggestion_history(args.friend, args.limit, format).await;
+    }
+    let options = config::SuggestOptions {
+        count: args.count.unwrap_or(1) as usize,
+        group: args.group,
+        filter: config::SuggestFilter {
+            level: args.level,
+            tag: args.tag,
+            exclude: args.exclude,
+            for_kind: args.for_kind,
+        },
+        strategy: args.strategy,
+        temperature: args.temperature,
+    };
+    let mut rng = config::suggestion_rng(args.seed);
+    if args.interactive {
+        config::suggest_interactively(options, &mut rng).await
+    } else {
+        config::suggest(options, format, &mut rng).await
+    }
 }